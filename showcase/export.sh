@@ -0,0 +1,11 @@
+#!/bin/sh
+# Exports every replay in showcase/replays to a GIF of the same name in showcase/gifs
+set -e
+
+cd "$(dirname "$0")/.."
+cargo build --release
+mkdir -p showcase/gifs
+
+for replay in showcase/replays/*.toml; do
+    ./target/release/terminal-tetris export "$replay" "showcase/gifs/$(basename "$replay" .toml).gif"
+done
@@ -0,0 +1,115 @@
+// Short effects drawn over a board: a flash where a piece locks, cleared lines wiping away,
+// and the stack filling up on a top out. They run on wall-clock time inside the renderer,
+// so the game never waits on them, except for the line clear delay the engine already has.
+use std::ops::Range;
+use std::time::Instant;
+use crate::game::Playfield;
+
+// Times are in microseconds
+const LOCK_FLASH_TIME: u128 = 150000;
+pub const TOP_OUT_ANIMATION_TIME: u128 = 1000000;
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum CellEffect {
+    // Drawn solid, whatever was there
+    Flash,
+    // Drawn empty, whatever was there
+    Erase,
+}
+
+pub struct PlayfieldAnimations {
+    pieces_locked: u32,
+    // The (row, column) of every block the locked piece covers
+    lock_flash: Option<(Vec<(usize, usize)>, Instant)>,
+    line_clear: Option<(Vec<usize>, Instant, u128)>,
+    top_out: Option<Instant>,
+}
+
+impl PlayfieldAnimations {
+    pub fn new() -> PlayfieldAnimations {
+        return PlayfieldAnimations {
+            pieces_locked: 0,
+            lock_flash: None,
+            line_clear: None,
+            top_out: None,
+        }
+    }
+
+    // Starts or stops animations for whatever changed since the last frame
+    pub fn observe(&mut self, playfield: &Playfield, line_clear_delay: u128) {
+        let state = &playfield.state;
+
+        if !playfield.animated {
+            *self = PlayfieldAnimations::new();
+            self.pieces_locked = state.pieces_locked;
+            return;
+        }
+
+        // A new game starts the count again
+        if state.pieces_locked > self.pieces_locked {
+            self.lock_flash = state.last_locked_piece.map(|piece| {
+                let blocks = piece.cells().iter().flat_map(|cell| state.board.blocks_under(*cell)).collect();
+                return (blocks, Instant::now());
+            });
+        }
+        self.pieces_locked = state.pieces_locked;
+
+        if state.clearing_rows.is_empty() {
+            self.line_clear = None;
+        } else if self.line_clear.is_none() {
+            self.line_clear = Some((state.clearing_rows.clone(), Instant::now(), line_clear_delay));
+        }
+
+        if !playfield.topped_out {
+            self.top_out = None;
+        } else if self.top_out.is_none() {
+            self.top_out = Some(Instant::now());
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        let lock_flash_running = self.lock_flash.as_ref().is_some_and(|(_, start)| start.elapsed().as_micros() < LOCK_FLASH_TIME);
+        let top_out_running = self.top_out.is_some_and(|start| start.elapsed().as_micros() < TOP_OUT_ANIMATION_TIME);
+        return lock_flash_running || self.line_clear.is_some() || top_out_running;
+    }
+
+    // Rows are counted over the whole board, buffer included, but the stack only fills up the visible rows
+    pub fn effect(&self, row: usize, column: usize, board_width: usize, visible_rows: Range<usize>) -> Option<CellEffect> {
+        // The stack fills from the bottom up
+        if let Some(start) = self.top_out {
+            let filled_rows = progress(start, TOP_OUT_ANIMATION_TIME) * visible_rows.len() as f64;
+            if (visible_rows.end - row) as f64 <= filled_rows.ceil() {
+                return Some(CellEffect::Flash);
+            }
+        }
+
+        // Cleared rows flash, then wipe away from the middle outwards
+        if let Some((rows, start, duration)) = &self.line_clear {
+            if rows.contains(&row) {
+                let wiped_half_width = progress(*start, *duration) * board_width as f64 / 2.0;
+                let distance_from_middle = (column as f64 + 0.5 - board_width as f64 / 2.0).abs();
+                return if distance_from_middle < wiped_half_width { Some(CellEffect::Erase) } else { Some(CellEffect::Flash) };
+            }
+        }
+
+        if let Some((blocks, start)) = &self.lock_flash {
+            let is_locked_cell = blocks.contains(&(row, column));
+            if is_locked_cell && start.elapsed().as_micros() < LOCK_FLASH_TIME {
+                return Some(CellEffect::Flash);
+            }
+        }
+
+        return None;
+    }
+}
+
+// How far through an animation is, from 0 to 1
+fn progress(start: Instant, duration: u128) -> f64 {
+    if duration == 0 {
+        return 1.0;
+    }
+
+    return (start.elapsed().as_micros() as f64 / duration as f64).min(1.0);
+}
@@ -0,0 +1,106 @@
+// Copies everything sent to the terminal into an asciicast v2 file, which asciinema and the
+// tools around it can play back. The renderer flushes once per frame, so every flush becomes
+// one timestamped event.
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::renderer::terminal_size;
+
+pub struct CastRecorder<W: Write> {
+    output: W,
+    cast: BufWriter<File>,
+    start: Instant,
+    // Written to the terminal since the last event
+    pending: Vec<u8>,
+    size: (u16, u16),
+}
+
+impl<W: Write> CastRecorder<W> {
+    pub fn create(output: W, path: &Path) -> Result<CastRecorder<W>, String> {
+        let file = File::create(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let mut recorder = CastRecorder {
+            output,
+            cast: BufWriter::new(file),
+            start: Instant::now(),
+            pending: Vec::new(),
+            size: terminal_size(),
+        };
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+        let env: Vec<String> = ["TERM", "SHELL"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok().map(|value| format!("{}: {}", json_string(name), json_string(&value))))
+            .collect();
+
+        writeln!(
+            recorder.cast,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"title\": \"Terminal Tetris\", \"env\": {{{}}}}}",
+            recorder.size.0,
+            recorder.size.1,
+            timestamp,
+            env.join(", "),
+        ).map_err(|error| format!("{}: {}", path.display(), error))?;
+
+        return Ok(recorder);
+    }
+
+    fn write_events(&mut self) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+
+        let size = terminal_size();
+        if size != self.size {
+            self.size = size;
+            writeln!(self.cast, "[{:.6}, \"r\", \"{}x{}\"]", time, size.0, size.1)?;
+        }
+
+        // A character split across two writes waits for the rest of it
+        let complete_length = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+
+        if complete_length == 0 {
+            return Ok(());
+        }
+
+        let text = String::from_utf8_lossy(&self.pending[..complete_length]).into_owned();
+        self.pending.drain(..complete_length);
+        writeln!(self.cast, "[{:.6}, \"o\", {}]", time, json_string(&text))?;
+        return self.cast.flush();
+    }
+}
+
+impl<W: Write> Write for CastRecorder<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.output.write(buffer)?;
+        self.pending.extend_from_slice(&buffer[..written]);
+        return Ok(written);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()?;
+        return self.write_events();
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+
+    for character in text.chars() {
+        match character {
+            '"'  => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if (character as u32) < 0x20 || character == '\u{7f}' => json.push_str(&format!("\\u{:04x}", character as u32)),
+            character => json.push(character),
+        }
+    }
+
+    json.push('"');
+    return json;
+}
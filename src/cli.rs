@@ -0,0 +1,201 @@
+// The command line. There are few enough options to read them by hand.
+use std::path::PathBuf;
+use crate::game::{mode_from_name, GameMode, MODE_NAMES};
+use crate::profile::{is_valid_profile_name, MAX_PROFILE_NAME_LENGTH};
+
+pub const USAGE: &str = "\
+Usage: terminal-tetris [OPTIONS]
+       terminal-tetris scores [MODE]
+       terminal-tetris check-config [FILE]
+       terminal-tetris export REPLAY GIF
+
+Options:
+  --mode MODE      Start straight into marathon, sprint, big, watch, versus or finesse
+  --seed NUMBER    Deal the same pieces every game
+  --config FILE    Read and save the settings here instead of the usual place
+  --theme NAME     Use this theme for the session
+  --profile NAME   Play as this profile for the session, starting it if it's new
+  --ascii          Draw with plain ASCII only
+  --replay FILE    Play back a recorded game
+  --record FILE    Record the session as an asciicast (asciinema v2) file
+  --bot            Let the AI play a marathon, sprint or big game
+  --no-menu        Skip the menus and quit once the game is over, marathon unless --mode says otherwise
+  -h, --help       Show this help
+
+Subcommands:
+  scores [MODE]        List the high scores, for every mode or just one
+  check-config [FILE]  Check a settings file for problems, the usual one if no file is given
+  export REPLAY GIF    Draw a replay into an animated GIF, without needing a terminal";
+
+pub enum CliCommand {
+    Play(PlayOptions),
+    ListHighScores(Option<String>),
+    CheckConfig(Option<PathBuf>),
+    Export { replay: PathBuf, gif: PathBuf },
+    Help,
+}
+
+pub struct PlayOptions {
+    // The mode to start in and whether it's big. None opens the main menu.
+    pub mode: Option<(GameMode, bool)>,
+    pub seed: Option<u64>,
+    pub config: Option<PathBuf>,
+    pub theme: Option<String>,
+    pub profile: Option<String>,
+    pub ascii: bool,
+    pub replay: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub bot: bool,
+    pub no_menu: bool,
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliCommand, String> {
+    let mut args = args.into_iter();
+    let mut options = PlayOptions {
+        mode: None,
+        seed: None,
+        config: None,
+        theme: None,
+        profile: None,
+        ascii: false,
+        replay: None,
+        record: None,
+        bot: false,
+        no_menu: false,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "scores" => {
+                let mode = args.next();
+                if let Some(mode) = &mode {
+                    parse_mode(mode)?;
+                }
+                return Ok(CliCommand::ListHighScores(mode));
+            }
+            "check-config" => return Ok(CliCommand::CheckConfig(args.next().map(PathBuf::from))),
+            "export" => {
+                let replay = PathBuf::from(value_of(&mut args, "export")?);
+                let gif = PathBuf::from(value_of(&mut args, "export")?);
+                return Ok(CliCommand::Export { replay, gif });
+            }
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            "--mode"    => options.mode = Some(parse_mode(&value_of(&mut args, "--mode")?)?),
+            "--seed"    => {
+                let seed = value_of(&mut args, "--seed")?;
+                options.seed = Some(seed.parse().map_err(|_| format!("'{}' isn't a seed, which is a whole number", seed))?);
+            }
+            "--config"  => options.config = Some(PathBuf::from(value_of(&mut args, "--config")?)),
+            "--theme"   => options.theme = Some(value_of(&mut args, "--theme")?),
+            "--profile" => {
+                let profile = value_of(&mut args, "--profile")?;
+                if !is_valid_profile_name(&profile) {
+                    return Err(format!("'{}' isn't a profile name, which is up to {} lower case letters, digits and dashes", profile, MAX_PROFILE_NAME_LENGTH));
+                }
+                options.profile = Some(profile);
+            }
+            "--replay"  => options.replay = Some(PathBuf::from(value_of(&mut args, "--replay")?)),
+            "--record"  => options.record = Some(PathBuf::from(value_of(&mut args, "--record")?)),
+            "--ascii"   => options.ascii = true,
+            "--bot"     => options.bot = true,
+            "--no-menu" => options.no_menu = true,
+            _           => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    // A replay brings its own mode and seed, and is played by whoever recorded it
+    if options.replay.is_some() && (options.mode.is_some() || options.seed.is_some() || options.bot) {
+        return Err(String::from("--replay can't be used with --mode, --seed or --bot"));
+    }
+
+    if options.mode.is_none() && options.replay.is_none() && (options.bot || options.no_menu) {
+        options.mode = Some((GameMode::Solo, false));
+    }
+
+    if options.bot && !matches!(options.mode, Some((GameMode::Solo | GameMode::Sprint, _))) {
+        return Err(String::from("--bot only works with marathon, sprint and big, the AI already plays in the others"));
+    }
+
+    return Ok(CliCommand::Play(options));
+}
+
+fn value_of(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    return args.next().ok_or(format!("{} needs a value", option));
+}
+
+fn parse_mode(name: &str) -> Result<(GameMode, bool), String> {
+    return mode_from_name(name).ok_or_else(|| {
+        let mode_names: Vec<&str> = MODE_NAMES.iter().map(|(_, _, mode_name)| *mode_name).collect();
+        format!("there's no mode called '{}', try one of {}", name, mode_names.join(", "))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliCommand, String> {
+        return parse_args(args.iter().map(|arg| arg.to_string()));
+    }
+
+    fn play_options(args: &[&str]) -> PlayOptions {
+        return match parse(args) {
+            Ok(CliCommand::Play(options)) => options,
+            Ok(_)                         => panic!("{:?} isn't a game", args),
+            Err(problem)                  => panic!("{:?}: {}", args, problem),
+        };
+    }
+
+    #[test]
+    fn no_arguments_open_the_menu() {
+        let options = play_options(&[]);
+        assert!(options.mode.is_none() && options.seed.is_none() && !options.bot && !options.no_menu);
+    }
+
+    #[test]
+    fn options_are_read_in_any_order() {
+        let options = play_options(&["--seed", "42", "--mode", "BIG", "--theme", "NES", "--profile", "alex", "--ascii", "--no-menu"]);
+
+        assert!(options.mode == Some((GameMode::Solo, true)));
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.theme.as_deref(), Some("NES"));
+        assert_eq!(options.profile.as_deref(), Some("alex"));
+        assert!(options.ascii && options.no_menu);
+    }
+
+    #[test]
+    fn the_bot_and_no_menu_play_marathon_unless_told_otherwise() {
+        assert!(play_options(&["--bot"]).mode == Some((GameMode::Solo, false)));
+        assert!(play_options(&["--no-menu", "--mode", "sprint"]).mode == Some((GameMode::Sprint, false)));
+    }
+
+    #[test]
+    fn subcommands_take_their_own_arguments() {
+        assert!(matches!(parse(&["scores"]), Ok(CliCommand::ListHighScores(None))));
+        assert!(matches!(parse(&["scores", "sprint"]), Ok(CliCommand::ListHighScores(Some(mode))) if mode == "sprint"));
+        assert!(matches!(parse(&["check-config"]), Ok(CliCommand::CheckConfig(None))));
+        assert!(matches!(parse(&["check-config", "my.toml"]), Ok(CliCommand::CheckConfig(Some(path))) if path.as_os_str() == "my.toml"));
+        assert!(matches!(parse(&["export", "game.toml", "game.gif"]), Ok(CliCommand::Export { replay, gif }) if replay.as_os_str() == "game.toml" && gif.as_os_str() == "game.gif"));
+        assert!(matches!(parse(&["--mode", "sprint", "--help"]), Ok(CliCommand::Help)));
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        for args in [
+            Vec::from(["--fast"]),
+            Vec::from(["play"]),
+            Vec::from(["--mode"]),
+            Vec::from(["--mode", "tetris"]),
+            Vec::from(["--seed", "-1"]),
+            Vec::from(["--seed", "lucky"]),
+            Vec::from(["--profile", "Not A Name"]),
+            Vec::from(["scores", "tetris"]),
+            Vec::from(["export", "game.toml"]),
+            Vec::from(["--replay", "game.toml", "--seed", "3"]),
+            Vec::from(["--bot", "--mode", "versus"]),
+        ] {
+            assert!(parse(&args).is_err(), "{:?} should be an error", args);
+        }
+        assert!(matches!(parse(&["--fast"]), Err(problem) if problem == "unknown option '--fast'"));
+    }
+}
@@ -0,0 +1,104 @@
+// Turns a replay into an animated GIF by playing it through the game with nobody watching, and
+// drawing what's on the board every EXPORT_FRAME_TIME of game time
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use crate::game::{Game, Renderer, UpdateOutcome};
+use crate::gif::GifEncoder;
+use crate::pixel_renderer::{Image, PixelRenderer, PALETTE};
+use crate::replay::Frame;
+
+// Times are in microseconds
+const EXPORT_FRAME_TIME: u128 = 100000;
+// How long the end of the game stays up before the animation loops
+const FINAL_FRAME_TIME: u128 = 3000000;
+// GIF delays are in hundredths of a second
+const MICROSECONDS_PER_DELAY_UNIT: u128 = 10000;
+
+// Frames that look the same as the one before are folded into it. Returns how many were written.
+pub fn export_gif(game: &mut Game, frames: Vec<Frame>, path: &Path) -> Result<usize, String> {
+    let problem_with_file = |error: std::io::Error| format!("{}: {}", path.display(), error);
+    let file = File::create(path).map_err(problem_with_file)?;
+
+    let mut renderer = PixelRenderer::new();
+    let mut writer = AnimationWriter { encoder: None, file: Some(BufWriter::new(file)), shown: None, delay_written: 0, frames_written: 0 };
+    let mut time: u128 = 0;
+    let mut next_capture_time: u128 = 0;
+
+    for frame in frames {
+        let update_outcome = game.update(&frame.keys, &frame.repeated_keys, &frame.delta_time);
+        time += frame.delta_time;
+
+        if update_outcome == UpdateOutcome::Exit {
+            break;
+        }
+
+        if time < next_capture_time {
+            continue;
+        }
+        next_capture_time += EXPORT_FRAME_TIME * (1 + (time - next_capture_time) / EXPORT_FRAME_TIME);
+
+        renderer.render(&game.state(), game.settings());
+        if let Some(image) = renderer.image() {
+            writer.show(image, time).map_err(|problem| format!("{}: {}", path.display(), problem))?;
+        }
+    }
+
+    return writer.finish(time + FINAL_FRAME_TIME).map_err(|problem| format!("{}: {}", path.display(), problem));
+}
+
+// Holds on to the image on screen until it changes, since only then is its delay known
+struct AnimationWriter {
+    encoder: Option<GifEncoder<BufWriter<File>>>,
+    // Until the first image says how big the animation is
+    file: Option<BufWriter<File>>,
+    shown: Option<(Image, u128)>,
+    // In GIF delay units, kept as a running total so rounding never adds up
+    delay_written: u128,
+    frames_written: usize,
+}
+
+impl AnimationWriter {
+    fn show(&mut self, image: &Image, time: u128) -> Result<(), String> {
+        if self.shown.as_ref().is_some_and(|(shown_image, _)| shown_image == image) {
+            return Ok(());
+        }
+
+        self.write_shown(time)?;
+
+        if let Some(file) = self.file.take() {
+            let encoder = GifEncoder::new(file, image.width as u16, image.height as u16, &PALETTE).map_err(|error| error.to_string())?;
+            self.encoder = Some(encoder);
+        }
+
+        self.shown = Some((image.clone(), time));
+        return Ok(());
+    }
+
+    fn write_shown(&mut self, until_time: u128) -> Result<(), String> {
+        let (encoder, (image, _)) = match (&mut self.encoder, &self.shown) {
+            (Some(encoder), Some(shown)) => (encoder, shown),
+            _ => return Ok(()),
+        };
+
+        let delay_until = (until_time + MICROSECONDS_PER_DELAY_UNIT / 2) / MICROSECONDS_PER_DELAY_UNIT;
+        let delay = (delay_until - self.delay_written).min(u16::MAX as u128);
+        encoder.add_frame(&image.pixels, delay as u16).map_err(|error| error.to_string())?;
+
+        self.delay_written += delay;
+        self.frames_written += 1;
+        return Ok(());
+    }
+
+    fn finish(mut self, end_time: u128) -> Result<usize, String> {
+        self.write_shown(end_time)?;
+
+        return match self.encoder {
+            Some(encoder) => {
+                encoder.finish().map_err(|error| error.to_string())?;
+                Ok(self.frames_written)
+            }
+            None => Err(String::from("the replay never showed a game to draw")),
+        };
+    }
+}
@@ -0,0 +1,95 @@
+use crossterm::style::Color;
+use unicode_width::UnicodeWidthChar;
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Default)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+}
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum Cell {
+    Symbol(char, Style),
+    // The right half of a double-width symbol. The terminal fills it in when the symbol is printed.
+    Continuation,
+}
+
+// A grid of terminal cells that a frame is drawn into before anything is sent to the terminal
+#[derive(Clone)]
+pub struct FrameBuffer {
+    pub width: u16,
+    pub height: u16,
+    cells: Vec<Cell>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u16, height: u16) -> FrameBuffer {
+        return FrameBuffer {
+            width,
+            height,
+            cells: vec![Cell::Symbol(' ', Style::default()); width as usize * height as usize],
+        }
+    }
+
+    pub fn cell(&self, column: u16, row: u16) -> Cell {
+        return self.cells[self.index(column, row)];
+    }
+
+    // Returns how many columns the text takes up
+    pub fn put_str(&mut self, column: u16, row: u16, text: &str) -> u16 {
+        return self.put_styled_str(column, row, text, Style::default());
+    }
+
+    // Returns how many columns the text takes up
+    pub fn put_styled_str(&mut self, column: u16, row: u16, text: &str, style: Style) -> u16 {
+        let mut current_column = column;
+        for symbol in text.chars() {
+            current_column += self.put_styled_char(current_column, row, symbol, style);
+        }
+
+        return current_column - column;
+    }
+
+    // Returns how many columns the symbol takes up
+    pub fn put_styled_char(&mut self, column: u16, row: u16, symbol: char, style: Style) -> u16 {
+        let symbol_width = symbol.width().unwrap_or(0) as u16;
+
+        if symbol_width == 0 || row >= self.height || column + symbol_width > self.width {
+            return symbol_width;
+        }
+
+        self.break_up_wide_symbol_at(column, row);
+        if symbol_width == 2 {
+            self.break_up_wide_symbol_at(column + 1, row);
+        }
+
+        let index = self.index(column, row);
+        self.cells[index] = Cell::Symbol(symbol, style);
+        if symbol_width == 2 {
+            self.cells[index + 1] = Cell::Continuation;
+        }
+
+        return symbol_width;
+    }
+
+    // Writing over half of a double-width symbol erases the other half
+    fn break_up_wide_symbol_at(&mut self, column: u16, row: u16) {
+        let index = self.index(column, row);
+
+        if self.cells[index] == Cell::Continuation {
+            self.cells[index - 1] = Cell::Symbol(' ', Style::default());
+            self.cells[index] = Cell::Symbol(' ', Style::default());
+        } else if column + 1 < self.width && self.cells[index + 1] == Cell::Continuation {
+            self.cells[index + 1] = Cell::Symbol(' ', Style::default());
+        }
+    }
+
+    fn index(&self, column: u16, row: u16) -> usize {
+        return row as usize * self.width as usize + column as usize;
+    }
+}
@@ -0,0 +1,1248 @@
+use std::path::PathBuf;
+use device_query::Keycode;
+use tetris_core::bot::{BotPlayer, HeuristicBot};
+use tetris_core::piece::Piece;
+use tetris_core::finesse::{FinesseTracker, FinesseTrainer};
+use tetris_core::tetris::{MoveOutcome, StackVisibility, Tetris, TetrisState, MAX_BOARD_WIDTH, MAX_PREVIEW_LENGTH, MAX_VISIBLE_BOARD_HEIGHT, MIN_BOARD_WIDTH, MIN_VISIBLE_BOARD_HEIGHT};
+use tetris_core::ticker::Ticker;
+use crate::animation::TOP_OUT_ANIMATION_TIME;
+use crate::high_scores::{format_time, high_scores_path, today, HighScore, HighScores};
+use crate::history::{append_history, history_path, HistoryEntry};
+use crate::hud::Hud;
+use crate::menu::{Menu, MenuItem, MenuOutcome};
+use crate::profile::{is_valid_profile_name, profile_names, profile_path, Profile, ProfileGame, TrainerResults, MAX_PROFILE_NAME_LENGTH};
+use crate::replay::{new_replay_path, Replay};
+use crate::stats::GameStats;
+use crate::settings::{stack_visibility_name, Controls, Settings, ARR_RANGE, BLOCK_STYLES, DAS_RANGE, FADE_SECONDS_RANGE, GAME_KEYS, LOCK_DELAY_RANGE, RANDOMIZERS, ROTATION_SYSTEMS, SOFT_DROP_FACTOR_RANGE, STACK_VISIBILITIES};
+use crate::theme::Theme;
+
+// How many suggested placements the hint overlay cycles up to: off, best, best and second best
+const MAX_HINTS_SHOWN: usize = 2;
+// Sprints are a race to clear this many lines
+const SPRINT_LINES: u32 = 40;
+
+#[derive(PartialEq)]
+enum PlayingState {
+    Running,
+    Stopped,
+    Paused,
+    Finished,
+    // The top-out animation is playing, after which the game ends
+    ToppingOut,
+    ChoosingTheme,
+    // Waiting for a key to bind to one of GAME_KEYS
+    BindingKey(usize),
+    ViewingProfile,
+    // Typing the name of a new profile, with what's been typed so far
+    NamingProfile(String),
+}
+
+impl PlayingState {
+    fn is_playing(&self) -> bool {
+        return matches!(self, PlayingState::Running | PlayingState::ToppingOut);
+    }
+}
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum GameMode {
+    Solo,
+    Sprint,
+    WatchAi,
+    VersusAi,
+    FinesseTrainer,
+}
+
+// What each mode is called on the command line, in the high scores and in replays. Big mode
+// is solo with 2x2 minos.
+pub const MODE_NAMES: [(GameMode, bool, &str); 6] = [
+    (GameMode::Solo,           false, "marathon"),
+    (GameMode::Sprint,         false, "sprint"),
+    (GameMode::Solo,           true,  "big"),
+    (GameMode::WatchAi,        false, "watch"),
+    (GameMode::VersusAi,       false, "versus"),
+    (GameMode::FinesseTrainer, false, "finesse"),
+];
+
+pub fn mode_name(mode: GameMode, big: bool) -> &'static str {
+    return MODE_NAMES.iter().find(|(named_mode, named_big, _)| *named_mode == mode && *named_big == big).map_or("marathon", |(_, _, name)| name);
+}
+
+pub fn mode_from_name(name: &str) -> Option<(GameMode, bool)> {
+    return MODE_NAMES.iter().find(|(_, _, mode_name)| mode_name.eq_ignore_ascii_case(name)).map(|(mode, big, _)| (*mode, *big));
+}
+
+// Menus get the arrow keys, Enter and Escape, whatever the controls are. Games get the keys
+// bound in the controls, plus Escape to pause.
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Escape,
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterclockwise,
+    Hold,
+    Pause,
+    Hint,
+    Control,
+    C,
+    // Any key pressed this frame, for binding controls
+    Pressed(Keycode),
+}
+
+#[derive(PartialEq)]
+pub enum UpdateOutcome {
+    Exit,
+    Render,
+    NothingSpecial,
+}
+
+// What the game does when a menu item is picked. Anything that only changes a setting is
+// handled by the menu item itself.
+#[derive(Clone)]
+pub enum Command {
+    Resume,
+    Stop,
+    Start(GameMode),
+    StartBig,
+    ChooseTheme,
+    BindKey(usize),
+    ResetControls,
+    ShowProfile,
+    NewProfile,
+    Quit,
+}
+
+fn main_menu() -> Menu {
+    return Menu::new("Welcome to Terminal Tetris!", Vec::from([
+        MenuItem::command("Start new Game",  Command::Start(GameMode::Solo)),
+        MenuItem::command("Sprint",          Command::Start(GameMode::Sprint)),
+        MenuItem::command("Big Mode",        Command::StartBig),
+        MenuItem::command("Watch AI",        Command::Start(GameMode::WatchAi)),
+        MenuItem::command("Versus AI",       Command::Start(GameMode::VersusAi)),
+        MenuItem::command("Finesse Trainer", Command::Start(GameMode::FinesseTrainer)),
+        MenuItem::submenu("Profile",         profile_menu),
+        MenuItem::submenu("Settings",        settings_menu),
+        MenuItem::command("Quit",            Command::Quit),
+    ]));
+}
+
+// The profiles to pick from are looked up whenever the menu opens
+fn profile_menu() -> Menu {
+    let names = profile_names();
+    let option_names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+    let names_to_find = names.clone();
+    let names_to_pick = names.clone();
+
+    return Menu::new("Profile", Vec::from([
+        MenuItem::command("Stats and Charts", Command::ShowProfile),
+        MenuItem::choice(
+            "Player",
+            &option_names,
+            move |settings| names_to_find.iter().position(|name| *name == settings.profile).unwrap_or(0),
+            move |settings, index| settings.profile = names_to_pick[index].clone(),
+        ).with_label(|settings| format!("Player: {}", settings.profile)),
+        MenuItem::command("New Profile", Command::NewProfile),
+        MenuItem::back("Back"),
+    ]));
+}
+
+fn settings_menu() -> Menu {
+    return Menu::new("Settings", Vec::from([
+        MenuItem::submenu("Handling", handling_menu),
+        MenuItem::submenu("Visuals",  visuals_menu),
+        MenuItem::submenu("Rules",    rules_menu),
+        MenuItem::submenu("Controls", controls_menu),
+        MenuItem::back("Back"),
+    ]));
+}
+
+// Times are shown in milliseconds
+fn handling_menu() -> Menu {
+    return Menu::new("Handling", Vec::from([
+        MenuItem::slider(
+            "DAS",
+            *DAS_RANGE.start() as i32..=*DAS_RANGE.end() as i32,
+            10,
+            |settings| settings.das as i32,
+            |settings, value| settings.das = value as u128,
+        ).with_label(|settings| format!("DAS: {} ms", settings.das)),
+        MenuItem::slider(
+            "ARR",
+            *ARR_RANGE.start() as i32..=*ARR_RANGE.end() as i32,
+            10,
+            |settings| settings.arr as i32,
+            |settings, value| settings.arr = value as u128,
+        ).with_label(|settings| format!("ARR: {} ms", settings.arr)),
+        MenuItem::slider(
+            "Soft Drop Factor",
+            *SOFT_DROP_FACTOR_RANGE.start() as i32..=*SOFT_DROP_FACTOR_RANGE.end() as i32,
+            1,
+            |settings| settings.soft_drop_factor as i32,
+            |settings, value| settings.soft_drop_factor = value as u32,
+        ).with_label(|settings| format!("Soft Drop Factor: {}x", settings.soft_drop_factor)),
+        MenuItem::back("Back"),
+    ]));
+}
+
+fn visuals_menu() -> Menu {
+    return Menu::new("Visuals", Vec::from([
+        MenuItem::command("Theme", Command::ChooseTheme)
+            .with_label(|settings| format!("Theme: {}", settings.theme.name)),
+        MenuItem::choice(
+            "Block Style",
+            &BLOCK_STYLES.map(|(_, name)| name),
+            |settings| BLOCK_STYLES.iter().position(|(style, _)| *style == settings.block_style).unwrap_or(0),
+            |settings, index| settings.block_style = BLOCK_STYLES[index].0,
+        ),
+        MenuItem::toggle("Ghost Piece", |settings| settings.ghost, |settings, value| settings.ghost = value),
+        MenuItem::slider(
+            "Next Pieces",
+            0..=MAX_PREVIEW_LENGTH as i32,
+            1,
+            |settings| settings.preview_length as i32,
+            |settings, value| settings.preview_length = value as usize,
+        ),
+        MenuItem::toggle("Animations", |settings| settings.animations, |settings, value| settings.animations = value),
+        MenuItem::choice(
+            "Stack",
+            &STACK_VISIBILITIES.map(|(_, name)| name),
+            |settings| STACK_VISIBILITIES.iter().position(|(_, name)| *name == stack_visibility_name(&settings.stack_visibility)).unwrap_or(0),
+            |settings, index| settings.set_stack_visibility(STACK_VISIBILITIES[index].0),
+        ),
+        MenuItem::slider(
+            "Fade Time",
+            *FADE_SECONDS_RANGE.start() as i32..=*FADE_SECONDS_RANGE.end() as i32,
+            1,
+            |settings| settings.fade_seconds as i32,
+            |settings, value| settings.set_fade_seconds(value as u32),
+        ).with_label(|settings| format!("Fade Time: {} s", settings.fade_seconds))
+            .enabled_when(|settings| matches!(settings.stack_visibility, StackVisibility::Fading(_))),
+        // There's nothing to flash while the stack is visible anyway
+        MenuItem::toggle("Flash on Line Clears", |settings| settings.flash_stack, |settings, value| settings.flash_stack = value)
+            .enabled_when(|settings| settings.stack_visibility != StackVisibility::Visible),
+        MenuItem::back("Back"),
+    ]));
+}
+
+fn rules_menu() -> Menu {
+    return Menu::new("Rules", Vec::from([
+        MenuItem::choice(
+            "Rotation",
+            &ROTATION_SYSTEMS.map(|(_, name)| name),
+            |settings| ROTATION_SYSTEMS.iter().position(|(system, _)| *system == settings.rotation_system).unwrap_or(0),
+            |settings, index| settings.rotation_system = ROTATION_SYSTEMS[index].0,
+        ),
+        MenuItem::choice(
+            "Randomizer",
+            &RANDOMIZERS.map(|(_, name)| name),
+            |settings| RANDOMIZERS.iter().position(|(randomizer, _)| *randomizer == settings.randomizer).unwrap_or(0),
+            |settings, index| settings.randomizer = RANDOMIZERS[index].0,
+        ),
+        MenuItem::slider(
+            "Lock Delay",
+            *LOCK_DELAY_RANGE.start() as i32..=*LOCK_DELAY_RANGE.end() as i32,
+            50,
+            |settings| settings.lock_delay as i32,
+            |settings, value| settings.lock_delay = value as u128,
+        ).with_label(|settings| match settings.lock_delay {
+            0          => String::from("Lock Delay: Off"),
+            lock_delay => format!("Lock Delay: {} ms", lock_delay),
+        }),
+        MenuItem::toggle("Hold", |settings| settings.hold, |settings, value| settings.hold = value),
+        MenuItem::slider(
+            "Board Width",
+            MIN_BOARD_WIDTH as i32..=MAX_BOARD_WIDTH as i32,
+            1,
+            |settings| settings.board_width as i32,
+            |settings, value| settings.board_width = value as usize,
+        ),
+        MenuItem::slider(
+            "Board Height",
+            MIN_VISIBLE_BOARD_HEIGHT as i32..=MAX_VISIBLE_BOARD_HEIGHT as i32,
+            1,
+            |settings| settings.board_height as i32,
+            |settings, value| settings.board_height = value as usize,
+        ),
+        MenuItem::back("Back"),
+    ]));
+}
+
+fn controls_menu() -> Menu {
+    let mut items: Vec<MenuItem> = GAME_KEYS
+        .iter()
+        .enumerate()
+        .map(|(index, (_, _, label))| {
+            MenuItem::command(label, Command::BindKey(index))
+                .with_label(move |settings| format!("{}: {}", label, settings.controls.describe(index)))
+        })
+        .collect();
+
+    items.push(MenuItem::command("Reset to Defaults", Command::ResetControls));
+    items.push(MenuItem::back("Back"));
+
+    return Menu::new("Controls", items);
+}
+
+fn pause_menu() -> Menu {
+    return Menu::new("Paused", Vec::from([
+        MenuItem::command("Resume",            Command::Resume),
+        MenuItem::command("Exit to Main Menu", Command::Stop),
+        MenuItem::command("Quit",              Command::Quit),
+    ]));
+}
+
+fn finished_menu(title: &str) -> Menu {
+    return Menu::new(title, Vec::from([
+        MenuItem::command("Back to Main Menu", Command::Stop),
+        MenuItem::command("Quit",              Command::Quit),
+    ]));
+}
+
+// Applies each theme as soon as it's highlighted, so the whole game previews it
+pub struct ThemePicker {
+    pub themes: Vec<Theme>,
+    pub selected_theme: usize,
+    previous_theme: Theme,
+}
+
+impl ThemePicker {
+    pub fn new(current_theme: &Theme) -> ThemePicker {
+        let themes = Theme::all();
+        let selected_theme = themes.iter().position(|theme| theme.name == current_theme.name).unwrap_or(0);
+
+        return ThemePicker {
+            themes,
+            selected_theme,
+            previous_theme: current_theme.clone(),
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        self.selected_theme = (self.selected_theme + 1) % self.themes.len();
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected_theme = (self.selected_theme + self.themes.len() - 1) % self.themes.len();
+    }
+
+    pub fn highlighted_theme(&self) -> &Theme {
+        return &self.themes[self.selected_theme];
+    }
+}
+
+pub struct Game<'a> {
+    playing_state: PlayingState,
+    mode: GameMode,
+    big: bool,
+    // Every game uses this seed if it's set, otherwise each gets a new one
+    seed: Option<u64>,
+    game_seed: u64,
+    // The bot plays the player's board, whatever the mode
+    autoplay: bool,
+    // Scripted sessions go straight back to the command line when the game ends
+    exit_when_finished: bool,
+    // How the last game ended
+    result: Option<String>,
+    // Games played by people leave a replay and can get a high score. Replays don't.
+    record_games: bool,
+    recording: Option<Replay>,
+    tetris: Tetris,
+    bot_tetris: Tetris,
+    bot_player: BotPlayer<HeuristicBot>,
+    hud: Hud,
+    bot_hud: Hud,
+    // How the player's board is being played, for the results screen
+    stats: GameStats,
+    // Loaded while the profile screen is open
+    viewed_profile: Option<Profile>,
+    finesse: FinesseTracker,
+    trainer: FinesseTrainer,
+    hint_bot: HeuristicBot,
+    hints_shown: usize,
+    lines_to_next_speed: u8,
+    line_clear_timer: u128,
+    top_out_timer: u128,
+    ticker: &'a mut Ticker,
+    // The open menu is on top, with the ones it was opened from underneath
+    menus: Vec<Menu>,
+    theme_picker: Option<ThemePicker>,
+    settings: Settings,
+    // Where settings are saved whenever a menu is closed. None if there's nowhere to keep them.
+    settings_path: Option<PathBuf>,
+}
+
+// A board and the numbers that go with it
+pub struct Playfield<'a> {
+    pub state: TetrisState,
+    pub hud: &'a Hud,
+    // Bot-driven boards move too fast for animations to be anything but noise
+    pub animated: bool,
+    pub topped_out: bool,
+}
+
+#[allow(clippy::large_enum_variant)]
+pub enum RenderState<'a> {
+    Running(Playfield<'a>),
+    Versus(Playfield<'a>, Playfield<'a>),
+    Training(Playfield<'a>, &'a FinesseTrainer),
+    InMenu(&'a Menu),
+    // The menu at the end of a game, with the stats of the player's board underneath
+    Results(&'a Menu, &'a GameStats),
+    // A profile's totals, personal bests and charts of recent games, by name
+    Profile(&'a str, &'a Profile),
+    ChoosingTheme(&'a ThemePicker),
+    // A message with nothing to pick, e.g. while waiting for a key to bind
+    Prompt(String),
+}
+
+pub trait Renderer {
+    fn render(&mut self, state: &RenderState, settings: &Settings);
+    // Whether the last frame is out of date for reasons other than the game, e.g. the terminal was resized or an animation is playing
+    fn needs_redraw(&self) -> bool;
+}
+
+impl Game<'_> {
+    pub fn new(ticker: &mut Ticker, bot_input_interval: u128, settings: Settings, settings_path: Option<PathBuf>) -> Game<'_> {
+        return Game {
+            playing_state: PlayingState::Stopped,
+            mode: GameMode::Solo,
+            big: false,
+            seed: None,
+            game_seed: 0,
+            autoplay: false,
+            exit_when_finished: false,
+            result: None,
+            record_games: true,
+            recording: None,
+            tetris: Tetris::new(),
+            bot_tetris: Tetris::new(),
+            bot_player: BotPlayer::new(HeuristicBot::new(), bot_input_interval),
+            hud: Hud::new(),
+            bot_hud: Hud::new(),
+            stats: GameStats::new(),
+            viewed_profile: None,
+            finesse: FinesseTracker::new(),
+            trainer: FinesseTrainer::new(),
+            hint_bot: HeuristicBot::new(),
+            hints_shown: 0,
+            lines_to_next_speed: 0,
+            line_clear_timer: 0,
+            top_out_timer: 0,
+            ticker,
+            menus: Vec::from([main_menu()]),
+            theme_picker: None,
+            settings,
+            settings_path,
+        }
+    }
+
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn set_autoplay(&mut self, autoplay: bool) {
+        self.autoplay = autoplay;
+    }
+
+    pub fn set_exit_when_finished(&mut self, exit_when_finished: bool) {
+        self.exit_when_finished = exit_when_finished;
+    }
+
+    pub fn set_record_games(&mut self, record_games: bool) {
+        self.record_games = record_games;
+    }
+
+    pub fn result(&self) -> Option<&str> {
+        return self.result.as_deref();
+    }
+
+    // In big mode every mino of the player's pieces covers 2x2 blocks. The player and the bot
+    // get the same pieces in versus.
+    pub fn start(&mut self, mode: GameMode, big: bool) {
+        self.playing_state = PlayingState::Running;
+        self.mode = mode;
+        self.big = big;
+        self.game_seed = self.seed.unwrap_or_else(rand::random);
+        self.tetris = self.new_tetris(big);
+        self.bot_tetris = self.new_tetris(false);
+        self.ticker.reset();
+        self.lines_to_next_speed = 0;
+        self.result = None;
+        self.tetris.set_preview_length(self.settings.preview_length);
+        self.bot_tetris.set_preview_length(self.settings.preview_length);
+        self.bot_player.reset();
+        self.line_clear_timer = 0;
+        if self.is_solo() && self.is_player_animated() {
+            self.tetris.set_line_clear_delay(self.settings.line_clear_delay);
+        }
+        if self.has_stack_rules() {
+            self.tetris.set_stack_visibility(self.settings.stack_visibility);
+            self.tetris.set_flash_stack(self.settings.flash_stack);
+        }
+        self.tetris.set_rotation_system(self.settings.rotation_system);
+        self.tetris.set_show_ghost(self.settings.ghost);
+        self.tetris.set_hold_enabled(self.settings.hold);
+        // The trainer leaves pieces where they are until the player drops them
+        if self.mode != GameMode::FinesseTrainer {
+            self.tetris.set_lock_delay(self.settings.lock_delay * 1000);
+        }
+        self.hud = Hud::new();
+        self.bot_hud = Hud::new();
+        self.stats = GameStats::new();
+        self.finesse = FinesseTracker::new();
+        self.finesse.start_piece(&self.tetris);
+        self.trainer = FinesseTrainer::new();
+        if self.mode == GameMode::FinesseTrainer {
+            self.trainer = self.load_trainer();
+            self.trainer.pick_target(&self.tetris);
+        }
+
+        // The trainer picks its targets at random, so its games can't be replayed
+        let is_replayable = self.is_solo() || self.mode == GameMode::VersusAi;
+        self.recording = if self.record_games && !self.is_bot_driven() && is_replayable {
+            Some(Replay::new(mode, big, self.game_seed, &self.settings))
+        } else {
+            None
+        };
+    }
+
+    fn new_tetris(&self, big: bool) -> Tetris {
+        return Tetris::with_board_size(self.settings.randomizer, self.game_seed, self.settings.board_width, self.settings.board_height, big);
+    }
+
+    fn on_piece_locked(&mut self) {
+        self.hud.observe(&self.tetris);
+        self.stats.observe(&self.tetris, &self.hud);
+        let finesse_result = self.finesse.finish_piece(&self.tetris);
+        self.stats.finesse_faults += finesse_result.as_ref().map_or(0, |finesse_result| finesse_result.faults);
+
+        if self.mode == GameMode::FinesseTrainer {
+            self.trainer.judge_piece(&self.tetris, finesse_result);
+            self.tetris.clear_board();
+            if self.record_games {
+                self.save_trainer_results();
+            }
+        }
+    }
+
+    fn on_piece_spawned(&mut self) {
+        if self.mode == GameMode::FinesseTrainer {
+            self.trainer.pick_target(&self.tetris);
+        }
+
+        self.finesse.start_piece(&self.tetris);
+    }
+
+    fn on_move_outcome(&mut self, move_outcome: &MoveOutcome) {
+        match move_outcome {
+            MoveOutcome::SpawnedNewPieceAndClearedLines(_) => {
+                self.on_piece_locked();
+                self.on_piece_spawned();
+            }
+            MoveOutcome::ClearingLines(_) => {
+                self.on_piece_locked();
+                self.line_clear_timer = 0;
+            }
+            _ => (),
+        }
+    }
+
+    fn on_piece_held(&mut self) {
+        self.stats.holds += 1;
+        self.finesse.start_piece(&self.tetris);
+
+        if self.mode == GameMode::FinesseTrainer {
+            self.trainer.pick_target(&self.tetris);
+        }
+    }
+
+    fn finish(&mut self, title: &str) {
+        self.finish_game(title, false);
+    }
+
+    // Marathons and big games are scored when the player tops out, sprints when they're done
+    fn finish_scored_game(&mut self, title: &str) {
+        self.finish_game(title, true);
+    }
+
+    // Games people play go into their profile and the history, and scored ones can make the
+    // high scores too
+    fn finish_game(&mut self, title: &str, is_scored: bool) {
+        self.stats.finish(&self.hud);
+
+        let is_played_by_person = self.record_games && !self.is_bot_driven();
+        let place = if is_scored && is_played_by_person { self.add_high_score() } else { None };
+        let is_personal_best = is_played_by_person && self.add_to_profile(is_scored);
+        if is_played_by_person {
+            self.add_to_history();
+        }
+
+        let title = match (place, is_personal_best) {
+            (Some(place), _) => format!("{} New high score, #{}!", title, place),
+            (None, true)     => format!("{} New personal best!", title),
+            (None, false)    => title.to_string(),
+        };
+
+        self.playing_state = PlayingState::Finished;
+        self.menus = Vec::from([finished_menu(&title)]);
+        self.result = Some(title);
+        self.save_replay();
+    }
+
+    // There's nowhere to show a failure once the game is over, so high scores, profiles, the
+    // history and replays are best effort, like the settings
+    fn add_high_score(&self) -> Option<usize> {
+        let path = high_scores_path()?;
+        let mut high_scores = HighScores::load(&path).ok()?;
+        let high_score = HighScore {
+            score: self.hud.score,
+            lines: self.hud.lines,
+            time: (self.hud.time / 1000) as u64,
+            seed: self.game_seed,
+            date: today(),
+        };
+
+        let place = high_scores.add(mode_name(self.mode, self.big), high_score)?;
+        high_scores.save(&path).ok()?;
+        return Some(place);
+    }
+
+    // Returns whether the game was a personal best
+    fn add_to_profile(&self, is_scored: bool) -> bool {
+        let path = match profile_path(&self.settings.profile) {
+            Some(path) => path,
+            None => return false,
+        };
+        let mut profile = match Profile::load(&path) {
+            Ok(profile) => profile,
+            Err(_) => return false,
+        };
+        let game = ProfileGame {
+            score: self.hud.score,
+            lines: self.hud.lines,
+            time: (self.hud.time / 1000) as u64,
+            date: today(),
+        };
+
+        let is_personal_best = profile.add_game(mode_name(self.mode, self.big), game, is_scored);
+        return profile.save(&path).is_ok() && is_personal_best;
+    }
+
+    fn load_profile(&self) -> Profile {
+        return profile_path(&self.settings.profile)
+            .and_then(|path| Profile::load(&path).ok())
+            .unwrap_or_else(Profile::new);
+    }
+
+    // The trainer carries on from the profile's earlier sessions
+    fn load_trainer(&self) -> FinesseTrainer {
+        let results = self.load_profile().finesse_trainer;
+        return FinesseTrainer::with_results(results.pieces as usize, results.clean_pieces as usize, &results.recent_pieces);
+    }
+
+    // Trainer sessions are left from the pause menu rather than finished, so every piece is saved
+    fn save_trainer_results(&self) {
+        let path = match profile_path(&self.settings.profile) {
+            Some(path) => path,
+            None => return,
+        };
+        let mut profile = match Profile::load(&path) {
+            Ok(profile) => profile,
+            Err(_) => return,
+        };
+
+        profile.finesse_trainer = TrainerResults {
+            pieces: self.trainer.pieces() as u32,
+            clean_pieces: self.trainer.clean_pieces() as u32,
+            recent_pieces: self.trainer.recent_pieces(),
+        };
+        let _ = profile.save(&path);
+    }
+
+    fn add_to_history(&self) {
+        if let Some(path) = history_path() {
+            let entry = HistoryEntry::new(mode_name(self.mode, self.big), &self.settings.profile, self.game_seed, today(), &self.stats);
+            let _ = append_history(&path, entry);
+        }
+    }
+
+    fn save_replay(&mut self) {
+        if let Some(replay) = self.recording.take() {
+            if let Some(path) = new_replay_path(&replay.mode) {
+                let _ = replay.save(&path);
+            }
+        }
+    }
+
+    fn pause(&mut self) {
+        self.playing_state = PlayingState::Paused;
+        self.menus = Vec::from([pause_menu()]);
+    }
+
+    fn stop(&mut self) {
+        self.playing_state = PlayingState::Stopped;
+        self.recording = None;
+        self.menus = Vec::from([main_menu()]);
+    }
+
+    // There's nowhere to show a failure mid-game, and the settings still apply until the game closes
+    fn save_settings(&self) {
+        if let Some(settings_path) = &self.settings_path {
+            let _ = self.settings.save(settings_path);
+        }
+    }
+
+    // Soft drop moves the piece this many microseconds apart, sped up from gravity by the soft drop factor
+    pub fn soft_drop_interval(&self) -> u128 {
+        return self.ticker.tick_interval_time / self.settings.soft_drop_factor.max(1) as u128;
+    }
+
+    fn on_player_topped_out(&mut self) -> UpdateOutcome {
+        if self.playing_state == PlayingState::Running && self.is_player_animated() {
+            self.playing_state = PlayingState::ToppingOut;
+            self.top_out_timer = 0;
+            return UpdateOutcome::Render;
+        }
+
+        match self.mode {
+            _ if self.autoplay       => self.finish(&format!("The AI topped out with {} points.", self.hud.score)),
+            GameMode::Solo           => self.finish_scored_game(&format!("Game Over. You scored {} points.", self.hud.score)),
+            GameMode::Sprint         => self.finish(&format!("You topped out with {} of {} lines.", self.hud.lines, SPRINT_LINES)),
+            GameMode::WatchAi        => self.finish("The AI topped out."),
+            GameMode::VersusAi       => self.finish("You topped out. The AI wins!"),
+            GameMode::FinesseTrainer => self.finish("Game Over"),
+        }
+
+        return UpdateOutcome::Render;
+    }
+
+    pub fn update(&mut self, keys: &Vec<Key>, repeated_keys: &[Key], delta_time: &u128) -> UpdateOutcome {
+        if let Some(recording) = self.recording.as_mut().filter(|_| self.playing_state.is_playing()) {
+            recording.start_frame(*delta_time);
+        }
+
+        let mut update_outcome = self.update_game(keys, repeated_keys, delta_time);
+
+        // In versus, whatever one side sends is queued up on the other side's board
+        if self.mode == GameMode::VersusAi && self.playing_state == PlayingState::Running {
+            let player_garbage = self.tetris.take_outgoing_garbage();
+            let bot_garbage = self.bot_tetris.take_outgoing_garbage();
+            self.tetris.receive_garbage(bot_garbage);
+            self.bot_tetris.receive_garbage(player_garbage);
+        }
+
+        if self.playing_state == PlayingState::Running && self.mode == GameMode::Sprint && self.hud.lines >= SPRINT_LINES {
+            let title = format!("Cleared {} lines in {}.", SPRINT_LINES, format_time((self.hud.time / 1000) as u64));
+            if self.autoplay {
+                self.finish(&title);
+            } else {
+                self.finish_scored_game(&title);
+            }
+            update_outcome = UpdateOutcome::Render;
+        }
+
+        if self.exit_when_finished && self.playing_state == PlayingState::Finished {
+            return UpdateOutcome::Exit;
+        }
+
+        return update_outcome;
+    }
+
+    fn update_game(&mut self, keys: &Vec<Key>, repeated_keys: &[Key], delta_time: &u128) -> UpdateOutcome {
+        let input_outcome = self.process_input(keys, repeated_keys, delta_time);
+
+        if input_outcome == UpdateOutcome::Exit {
+            return UpdateOutcome::Exit;
+        }
+
+        if self.playing_state == PlayingState::ToppingOut {
+            self.top_out_timer += delta_time;
+            if self.top_out_timer >= TOP_OUT_ANIMATION_TIME {
+                return self.on_player_topped_out();
+            }
+            return input_outcome;
+        }
+
+        if self.playing_state != PlayingState::Running {
+            return input_outcome;
+        }
+
+        let lock_outcome = self.tetris.pass_time(*delta_time);
+        match lock_outcome {
+            MoveOutcome::GameOver       => return self.on_player_topped_out(),
+            MoveOutcome::NothingSpecial => (),
+            _ => {
+                self.on_move_outcome(&lock_outcome);
+                self.ticker.reset_tick_timer();
+            }
+        }
+        let input_outcome = if lock_outcome == MoveOutcome::NothingSpecial { input_outcome } else { UpdateOutcome::Render };
+
+        // Blocks fade and flash with time rather than with input, so every frame might look different
+        let stack_changes_over_time = self.has_stack_rules()
+            && (matches!(self.settings.stack_visibility, StackVisibility::Fading(_)) || self.settings.flash_stack);
+        let input_outcome = if stack_changes_over_time { UpdateOutcome::Render } else { input_outcome };
+
+        let hud_changed = self.hud.update(delta_time) | self.bot_hud.update(delta_time);
+        let input_outcome = if hud_changed { UpdateOutcome::Render } else { input_outcome };
+
+        if self.is_bot_driven() {
+            let bot_outcome = self.bot_player.update(&mut self.tetris, delta_time);
+            self.hud.observe(&self.tetris);
+            self.stats.observe(&self.tetris, &self.hud);
+
+            return match bot_outcome {
+                Some(MoveOutcome::GameOver) => self.on_player_topped_out(),
+                Some(_)                     => UpdateOutcome::Render,
+                None                        => input_outcome,
+            };
+        }
+
+        // The bot moving only means there's something to draw, the player's gravity still has to run
+        let mut input_outcome = input_outcome;
+        if self.mode == GameMode::VersusAi {
+            let bot_outcome = self.bot_player.update(&mut self.bot_tetris, delta_time);
+            self.bot_hud.observe(&self.bot_tetris);
+
+            match bot_outcome {
+                Some(MoveOutcome::GameOver) => {
+                    self.finish("The AI topped out. You win!");
+                    return UpdateOutcome::Render;
+                }
+                Some(_) => input_outcome = UpdateOutcome::Render,
+                None    => {}
+            }
+        }
+
+        // The trainer is about placement, not speed, so pieces only fall when the player drops them
+        if self.mode == GameMode::FinesseTrainer {
+            return input_outcome;
+        }
+
+        // Gravity waits for the cleared lines to go
+        if self.tetris.is_clearing_lines() {
+            self.line_clear_timer += delta_time;
+            if self.line_clear_timer < self.tetris.line_clear_delay() {
+                return input_outcome;
+            }
+
+            let move_outcome = self.tetris.finish_line_clear();
+            self.ticker.reset_tick_timer();
+            return match move_outcome {
+                MoveOutcome::GameOver => self.on_player_topped_out(),
+                _ => {
+                    self.on_piece_spawned();
+                    UpdateOutcome::Render
+                }
+            };
+        }
+
+        let should_tick = self.ticker.update(delta_time);
+
+        if should_tick {
+            let move_outcome = self.tetris.move_down_and_stick();
+            match move_outcome {
+                MoveOutcome::GameOver => return self.on_player_topped_out(),
+                _ => self.on_move_outcome(&move_outcome),
+            }
+
+            return UpdateOutcome::Render;
+        }
+
+        return input_outcome;
+    }
+
+    // Hidden stacks are a challenge for people, and the trainer needs to see the stack it's teaching on
+    fn has_stack_rules(&self) -> bool {
+        return self.is_solo() || self.mode == GameMode::VersusAi;
+    }
+
+    // Only boards a person is playing on get animations
+    fn is_player_animated(&self) -> bool {
+        return self.settings.animations && !self.is_bot_driven() && (self.is_solo() || self.mode == GameMode::VersusAi);
+    }
+
+    fn is_solo(&self) -> bool {
+        return self.mode == GameMode::Solo || self.mode == GameMode::Sprint;
+    }
+
+    fn is_bot_driven(&self) -> bool {
+        return self.mode == GameMode::WatchAi || self.autoplay;
+    }
+
+    pub fn settings(&self) -> &Settings {
+        return &self.settings;
+    }
+
+    fn hints(&self) -> Vec<Piece> {
+        if self.hints_shown == 0 || self.tetris.is_clearing_lines() {
+            return Vec::new();
+        }
+
+        let placements = self.tetris.reachable_placements()
+            .into_iter()
+            .filter(|placement| !placement.uses_hold())
+            .collect();
+
+        return self.hint_bot.rank_placements(placements, self.tetris.board())
+            .into_iter()
+            .take(self.hints_shown)
+            .map(|(_, placement)| placement.piece)
+            .collect();
+    }
+
+    // Every state that shows a menu opens one first, and closing the last one leaves that state
+    fn current_menu(&self) -> &Menu {
+        return self.menus.last().expect("a menu is always open outside of games");
+    }
+
+    fn player_playfield(&self, state: TetrisState) -> Playfield<'_> {
+        return Playfield {
+            state,
+            hud: &self.hud,
+            animated: self.is_player_animated(),
+            topped_out: self.playing_state == PlayingState::ToppingOut,
+        }
+    }
+
+    pub fn state(&self) -> RenderState<'_> {
+        match self.playing_state {
+            PlayingState::Running | PlayingState::ToppingOut => match self.mode {
+                GameMode::VersusAi => {
+                    let mut tetris_state = self.tetris.state();
+                    tetris_state.hints = self.hints();
+                    let bot_playfield = Playfield { state: self.bot_tetris.state(), hud: &self.bot_hud, animated: false, topped_out: false };
+                    RenderState::Versus(self.player_playfield(tetris_state), bot_playfield)
+                }
+                GameMode::FinesseTrainer => {
+                    let mut tetris_state = self.tetris.state();
+                    self.trainer.draw_target(&mut tetris_state.board);
+                    RenderState::Training(self.player_playfield(tetris_state), &self.trainer)
+                }
+                GameMode::Solo | GameMode::Sprint if !self.autoplay => {
+                    let mut tetris_state = self.tetris.state();
+                    tetris_state.hints = self.hints();
+                    RenderState::Running(self.player_playfield(tetris_state))
+                }
+                GameMode::Solo | GameMode::Sprint | GameMode::WatchAi => RenderState::Running(self.player_playfield(self.tetris.state())),
+            },
+            PlayingState::Finished => RenderState::Results(self.current_menu(), &self.stats),
+            PlayingState::Paused | PlayingState::Stopped => RenderState::InMenu(self.current_menu()),
+            PlayingState::BindingKey(index) => RenderState::Prompt(format!("Press a key for {}, or Escape to cancel", GAME_KEYS[index].2)),
+            PlayingState::NamingProfile(ref name) => RenderState::Prompt(format!("New profile name: {}_  (Enter to create, Escape to cancel)", name)),
+            PlayingState::ViewingProfile => match &self.viewed_profile {
+                Some(profile) => RenderState::Profile(&self.settings.profile, profile),
+                None          => RenderState::InMenu(self.current_menu()),
+            },
+            PlayingState::ChoosingTheme => match &self.theme_picker {
+                Some(theme_picker) => RenderState::ChoosingTheme(theme_picker),
+                None               => RenderState::InMenu(self.current_menu()),
+            },
+        }
+    }
+
+    fn process_input(&mut self, keys: &Vec<Key>, repeated_keys: &[Key], delta_time: &u128) -> UpdateOutcome {
+        if keys.contains(&Key::Control) && keys.contains(&Key::C) {
+            return UpdateOutcome::Exit;
+        }
+
+        let mut update_outcome: UpdateOutcome = UpdateOutcome::NothingSpecial;
+
+        for key in keys {
+            // Keys are recorded before they're acted on, so the one that ends a game is kept
+            let was_playing = self.playing_state.is_playing();
+            let was_paused = self.playing_state == PlayingState::Paused;
+            if let Some(recording) = self.recording.as_mut().filter(|_| was_playing) {
+                recording.record_key(key, repeated_keys.contains(key));
+            }
+
+            match (&self.playing_state, key) {
+                (PlayingState::Running, key) if self.is_bot_driven() => {
+                    match key {
+                        Key::Pause  => self.pause(),
+                        Key::Escape => self.pause(),
+                        _           => (),
+                    }
+                }
+
+                (PlayingState::Running, key) => {
+                    let could_hold_piece = self.tetris.can_hold_piece() && !self.tetris.is_clearing_lines();
+                    let move_outcome = match key {
+                        Key::MoveLeft               => self.tetris.try_and_move_left(),
+                        Key::MoveRight              => self.tetris.try_and_move_right(),
+                        Key::SoftDrop               => self.tetris.try_and_move_down(),
+                        Key::RotateClockwise        => self.tetris.try_and_rotate_clockwise(),
+                        Key::RotateCounterclockwise => self.tetris.try_and_rotate_counterclockwise(),
+                        Key::Hold                   => self.tetris.hold_piece(),
+                        Key::HardDrop               => self.tetris.slam(),
+                        Key::Pause                  => { self.pause(); MoveOutcome::NothingSpecial },
+                        Key::Escape                 => { self.pause(); MoveOutcome::NothingSpecial },
+                        Key::Hint                   => { self.hints_shown = (self.hints_shown + 1) % (MAX_HINTS_SHOWN + 1); MoveOutcome::NothingSpecial },
+                        _                           => MoveOutcome::NothingSpecial,
+                    };
+
+                    let counts_for_finesse = matches!(key, Key::MoveLeft | Key::MoveRight | Key::RotateClockwise | Key::RotateCounterclockwise);
+                    if counts_for_finesse && !repeated_keys.contains(key) {
+                        self.finesse.record_key_press();
+                    }
+
+                    let counts_as_key_press = counts_for_finesse || matches!(key, Key::SoftDrop | Key::HardDrop | Key::Hold);
+                    if counts_as_key_press && !repeated_keys.contains(key) {
+                        self.stats.key_presses += 1;
+                    }
+
+                    match key {
+                        Key::Hold if could_hold_piece => self.on_piece_held(),
+                        Key::Hold => (),
+                        _ => self.on_move_outcome(&move_outcome),
+                    }
+
+                    match move_outcome {
+                        MoveOutcome::SpawnedNewPieceAndClearedLines(cleared_lines) | MoveOutcome::ClearingLines(cleared_lines) => {
+                            self.lines_to_next_speed += cleared_lines;
+                            if self.lines_to_next_speed > 10 {
+                                self.lines_to_next_speed -= 10;
+                                self.ticker.increase_tick_speed();
+                            }
+                            self.ticker.reset_tick_timer();
+                        },
+                        MoveOutcome::MadeContactOnBottom => self.ticker.reset_tick_timer(),
+                        MoveOutcome::GameOver => { return self.on_player_topped_out() },
+                        _                                => (),
+                    }
+                }
+
+                (PlayingState::ToppingOut, _) => (),
+
+                (PlayingState::Paused, Key::Pause) => self.playing_state = PlayingState::Running,
+
+                (PlayingState::Paused | PlayingState::Stopped | PlayingState::Finished, key) => {
+                    let menu_outcome = match self.menus.last_mut() {
+                        Some(menu) => menu.handle_key(key, &mut self.settings),
+                        None       => MenuOutcome::Nothing,
+                    };
+
+                    match menu_outcome {
+                        MenuOutcome::Nothing => (),
+                        MenuOutcome::Open(menu) => self.menus.push(menu),
+                        MenuOutcome::Back if self.menus.len() > 1 => {
+                            self.menus.pop();
+                            self.save_settings();
+                        }
+                        MenuOutcome::Back => match self.playing_state {
+                            PlayingState::Paused   => self.playing_state = PlayingState::Running,
+                            PlayingState::Finished => self.stop(),
+                            _                      => (),
+                        },
+                        MenuOutcome::Command(command) => match command {
+                            Command::Resume       => self.playing_state = PlayingState::Running,
+                            Command::Stop         => self.stop(),
+                            Command::Start(mode)  => self.start(mode, false),
+                            Command::StartBig     => self.start(GameMode::Solo, true),
+                            Command::ChooseTheme  => {
+                                self.theme_picker = Some(ThemePicker::new(&self.settings.theme));
+                                self.playing_state = PlayingState::ChoosingTheme;
+                            }
+                            // The rest of this frame's keys include the Enter that picked this, which mustn't get bound
+                            Command::BindKey(index) => {
+                                self.playing_state = PlayingState::BindingKey(index);
+                                return UpdateOutcome::Render;
+                            }
+                            Command::ResetControls => self.settings.controls = Controls::new(),
+                            Command::ShowProfile   => {
+                                self.viewed_profile = Some(self.load_profile());
+                                self.playing_state = PlayingState::ViewingProfile;
+                            }
+                            // Like binding keys, the Enter that picked this mustn't be taken as the name being done
+                            Command::NewProfile    => {
+                                self.playing_state = PlayingState::NamingProfile(String::new());
+                                return UpdateOutcome::Render;
+                            }
+                            Command::Quit         => return UpdateOutcome::Exit,
+                        },
+                    }
+                }
+
+                // Menus are only open while stopped, so that's where binding always returns to
+                (PlayingState::BindingKey(_), Key::Escape) => self.playing_state = PlayingState::Stopped,
+
+                (PlayingState::BindingKey(index), Key::Pressed(keycode)) => {
+                    self.settings.controls.bindings[*index] = Vec::from([*keycode]);
+                    self.playing_state = PlayingState::Stopped;
+                    self.save_settings();
+                }
+
+                (PlayingState::BindingKey(_), _) => continue,
+
+                (PlayingState::ViewingProfile, Key::Escape | Key::Enter) => {
+                    self.viewed_profile = None;
+                    self.playing_state = PlayingState::Stopped;
+                }
+
+                (PlayingState::ViewingProfile, _) => continue,
+
+                (PlayingState::NamingProfile(_), Key::Escape) => self.playing_state = PlayingState::Stopped,
+
+                (PlayingState::NamingProfile(name), Key::Enter) => {
+                    if !is_valid_profile_name(name) {
+                        continue;
+                    }
+
+                    // The new profile gets a file straight away, so it's there to pick from
+                    if let Some(path) = profile_path(name).filter(|path| !path.exists()) {
+                        let _ = Profile::new().save(&path);
+                    }
+                    self.settings.profile = name.clone();
+                    self.save_settings();
+
+                    // The profile menu it was named from is rebuilt to include it
+                    self.menus.pop();
+                    self.menus.push(profile_menu());
+                    self.playing_state = PlayingState::Stopped;
+                }
+
+                (PlayingState::NamingProfile(name), Key::Pressed(keycode)) => {
+                    let mut name = name.clone();
+                    match (keycode, profile_name_character(keycode)) {
+                        (Keycode::Backspace, _) => { name.pop(); }
+                        (_, Some(character)) if name.len() < MAX_PROFILE_NAME_LENGTH => name.push(character),
+                        _ => continue,
+                    }
+                    self.playing_state = PlayingState::NamingProfile(name);
+                }
+
+                (PlayingState::NamingProfile(_), _) => continue,
+
+                (PlayingState::ChoosingTheme, key) => {
+                    let theme_picker = match &mut self.theme_picker {
+                        Some(theme_picker) => theme_picker,
+                        None => { self.playing_state = PlayingState::Stopped; continue }
+                    };
+
+                    match key {
+                        Key::Up     => theme_picker.move_up(),
+                        Key::Down   => theme_picker.move_down(),
+                        Key::Escape => {
+                            self.settings.theme = theme_picker.previous_theme.clone();
+                            self.theme_picker = None;
+                            self.playing_state = PlayingState::Stopped;
+                        }
+                        Key::Enter  => {
+                            self.theme_picker = None;
+                            self.playing_state = PlayingState::Stopped;
+                            self.save_settings();
+                        }
+                        _ => (),
+                    }
+
+                    if let Some(theme_picker) = &self.theme_picker {
+                        self.settings.theme = theme_picker.highlighted_theme().clone();
+                    }
+                }
+            };
+
+            // A game started or resumed partway through a frame plays out the rest of it. Resuming
+            // is recorded as Pause, which is how playback gets back out of the pause.
+            if !was_playing && self.playing_state.is_playing() {
+                if let Some(recording) = self.recording.as_mut() {
+                    let is_resuming = was_paused && recording.has_frames();
+                    recording.start_frame(*delta_time);
+                    if is_resuming {
+                        recording.record_key(&Key::Pause, false);
+                    }
+                }
+            }
+
+            update_outcome = UpdateOutcome::Render;
+        }
+
+        return update_outcome;
+    }
+}
+
+// Letters are typed in lower case, since that's all profile names are made of
+fn profile_name_character(keycode: &Keycode) -> Option<char> {
+    let name = keycode.to_string();
+
+    return match name.as_str() {
+        "Minus" => Some('-'),
+        letter if letter.len() == 1 => letter.chars().next().map(|character| character.to_ascii_lowercase()),
+        digit if digit.len() == 4 && digit.starts_with("Key") => digit.chars().last(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetris_core::piece::BlockType;
+
+    const FRAME_TIME: u128 = 16667;
+
+    fn new_game(ticker: &mut Ticker, record_games: bool) -> Game<'_> {
+        let mut game = Game::new(ticker, 50000, Settings::new(), None);
+        game.set_record_games(record_games);
+        game.set_seed(Some(7));
+        game.start(GameMode::Solo, false);
+        return game;
+    }
+
+    // Everything about the game that playback has to get the same
+    fn game_state(game: &Game) -> (Vec<Vec<BlockType>>, String, u32, u32, u128) {
+        let board = game.tetris.board().blocks.iter().map(|row| row.iter().map(|block| block.block_type).collect()).collect();
+        return (board, format!("{:?}", game.tetris.active_piece()), game.hud.score, game.hud.lines, game.hud.time);
+    }
+
+    #[test]
+    fn replays_of_paused_games_play_back_the_same() {
+        let mut frames: Vec<(u128, Vec<Key>)> = Vec::new();
+        for index in 0..300 {
+            let keys = match index % 60 {
+                10 => Vec::from([Key::MoveLeft]),
+                20 => Vec::from([Key::RotateClockwise]),
+                59 => Vec::from([Key::HardDrop]),
+                _  => Vec::new(),
+            };
+            frames.push((FRAME_TIME, keys));
+        }
+        // Paused mid-fall, with the menu moved around and a long wait before resuming
+        frames.push((FRAME_TIME, Vec::from([Key::MoveRight, Key::Pause, Key::Down])));
+        frames.extend([(2000000, Vec::from([Key::Down])), (2000000, Vec::from([Key::Up])), (2000000, Vec::new())]);
+        frames.push((FRAME_TIME, Vec::from([Key::Pause, Key::MoveRight])));
+        for _ in 0..300 {
+            frames.push((FRAME_TIME, Vec::new()));
+        }
+        frames.push((FRAME_TIME, Vec::from([Key::Escape])));
+        frames.push((FRAME_TIME, Vec::from([Key::Enter, Key::HardDrop])));
+        for _ in 0..100 {
+            frames.push((FRAME_TIME, Vec::new()));
+        }
+
+        let mut ticker = Ticker::new(1000000, 100000, 50000);
+        let mut game = new_game(&mut ticker, true);
+        for (delta_time, keys) in &frames {
+            game.update(keys, &[], delta_time);
+        }
+        let replay = game.recording.take().unwrap();
+        let played_state = game_state(&game);
+
+        let mut playback_ticker = Ticker::new(1000000, 100000, 50000);
+        let mut playback = new_game(&mut playback_ticker, false);
+        for frame in replay.frames().unwrap() {
+            playback.update(&frame.keys, &frame.repeated_keys, &frame.delta_time);
+        }
+
+        assert!(game.hud.pieces > 5);
+        assert_eq!(game_state(&playback), played_state);
+    }
+}
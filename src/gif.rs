@@ -0,0 +1,279 @@
+// Just enough of a GIF89a encoder for exporting replays: one 16 colour palette for the whole
+// animation, full frames, and looping forever.
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+
+pub type Palette = [(u8, u8, u8); 16];
+
+// Codes start one bit wider than the palette indices, and never get wider than 12 bits
+const MIN_CODE_SIZE: u8 = 4;
+const MAX_CODES: u16 = 4096;
+const MAX_SUB_BLOCK_LENGTH: usize = 255;
+
+pub struct GifEncoder<W: Write> {
+    output: W,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifEncoder<W> {
+    pub fn new(mut output: W, width: u16, height: u16, palette: &Palette) -> io::Result<GifEncoder<W>> {
+        output.write_all(b"GIF89a")?;
+        output.write_all(&width.to_le_bytes())?;
+        output.write_all(&height.to_le_bytes())?;
+        // A global colour table of 2^(3 + 1) colours, with 8 bits per primary
+        output.write_all(&[0b1111_0011, 0, 0])?;
+        for (red, green, blue) in palette {
+            output.write_all(&[*red, *green, *blue])?;
+        }
+
+        // The Netscape extension, set to loop forever
+        output.write_all(&[0x21, 0xff, 0x0b])?;
+        output.write_all(b"NETSCAPE2.0")?;
+        output.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        return Ok(GifEncoder { output, width, height });
+    }
+
+    // `pixels` are palette indices, row by row. The delay is in hundredths of a second.
+    pub fn add_frame(&mut self, pixels: &[u8], delay: u16) -> io::Result<()> {
+        // Graphic control extension: leave the frame in place, no transparency
+        self.output.write_all(&[0x21, 0xf9, 0x04, 0b0000_0100])?;
+        self.output.write_all(&delay.to_le_bytes())?;
+        self.output.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor covering the whole canvas, using the global colour table
+        self.output.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.output.write_all(&self.width.to_le_bytes())?;
+        self.output.write_all(&self.height.to_le_bytes())?;
+        self.output.write_all(&[0x00])?;
+
+        self.output.write_all(&[MIN_CODE_SIZE])?;
+        for sub_block in lzw_encode(pixels).chunks(MAX_SUB_BLOCK_LENGTH) {
+            self.output.write_all(&[sub_block.len() as u8])?;
+            self.output.write_all(sub_block)?;
+        }
+        return self.output.write_all(&[0x00]);
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.output.write_all(&[0x3b])?;
+        return self.output.flush();
+    }
+}
+
+// Variable-width LZW, starting over with a clear code whenever the table fills up
+fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+    let clear_code: u16 = 1 << MIN_CODE_SIZE;
+    let end_code = clear_code + 1;
+
+    let mut bits = BitWriter::new();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut prefix: Option<u16> = None;
+
+    bits.write(clear_code, code_size);
+
+    for &pixel in pixels {
+        let current = match prefix {
+            Some(current) => current,
+            None => {
+                prefix = Some(pixel as u16);
+                continue;
+            }
+        };
+
+        if let Some(&code) = table.get(&(current, pixel)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        bits.write(current, code_size);
+
+        if next_code < MAX_CODES {
+            table.insert((current, pixel), next_code);
+            next_code += 1;
+            // The decoder adds its entries a code behind, so it widens its codes one code later
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            bits.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = MIN_CODE_SIZE + 1;
+        }
+
+        prefix = Some(pixel as u16);
+    }
+
+    if let Some(current) = prefix {
+        bits.write(current, code_size);
+    }
+    bits.write(end_code, code_size);
+
+    return bits.finish();
+}
+
+// Packs codes least significant bit first, as GIF wants them
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    buffered_bits: u8,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        return BitWriter { bytes: Vec::new(), buffer: 0, buffered_bits: 0 };
+    }
+
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.buffered_bits;
+        self.buffered_bits += size;
+
+        while self.buffered_bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffered_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.buffered_bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        return self.bytes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Decoded {
+        pixels: Vec<u8>,
+        clear_codes: usize,
+        widest_code_size: u8,
+    }
+
+    // A plain GIF LZW decoder, written from the spec rather than from the encoder
+    fn lzw_decode(data: &[u8]) -> Decoded {
+        let clear_code = 1 << MIN_CODE_SIZE;
+        let end_code = clear_code + 1;
+        let initial_table: Vec<Vec<u8>> = (0..clear_code + 2).map(|code| Vec::from([code as u8])).collect();
+
+        let mut decoded = Decoded { pixels: Vec::new(), clear_codes: 0, widest_code_size: 0 };
+        let mut table = initial_table.clone();
+        let mut code_size = MIN_CODE_SIZE + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut bit_position = 0;
+
+        loop {
+            let mut code = 0;
+            for bit in 0..code_size as usize {
+                let byte = data[(bit_position + bit) / 8];
+                code |= (((byte >> ((bit_position + bit) % 8)) & 1) as usize) << bit;
+            }
+            bit_position += code_size as usize;
+            decoded.widest_code_size = decoded.widest_code_size.max(code_size);
+
+            if code == clear_code {
+                decoded.clear_codes += 1;
+                table = initial_table.clone();
+                code_size = MIN_CODE_SIZE + 1;
+                previous = None;
+                continue;
+            }
+            if code == end_code {
+                return decoded;
+            }
+
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) if code == table.len() => [previous.as_slice(), &previous[..1]].concat(),
+                _ => panic!("code {} isn't in the table", code),
+            };
+            decoded.pixels.extend(&entry);
+
+            if let Some(previous) = previous {
+                if table.len() < MAX_CODES as usize {
+                    table.push([previous.as_slice(), &entry[..1]].concat());
+                }
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            previous = Some(entry);
+        }
+    }
+
+    // Noisy enough that the table fills up and has to start over several times
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state: u32 = 12345;
+        return (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                return (state >> 16) as u8 % 16;
+            })
+            .collect();
+    }
+
+    #[test]
+    fn lzw_round_trips_short_and_repetitive_input() {
+        for pixels in [Vec::from([3]), Vec::from([1, 2, 1, 2, 1, 2, 1]), Vec::from([7; 1000]), (0..16).cycle().take(500).collect()] {
+            let decoded = lzw_decode(&lzw_encode(&pixels));
+            assert_eq!(decoded.pixels, pixels);
+            assert_eq!(decoded.clear_codes, 1);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips_input_that_fills_the_table() {
+        let pixels = noise(100000);
+        let decoded = lzw_decode(&lzw_encode(&pixels));
+
+        assert_eq!(decoded.widest_code_size, 12);
+        assert!(decoded.clear_codes > 2);
+        assert_eq!(decoded.pixels, pixels);
+    }
+
+    #[test]
+    fn files_have_the_header_palette_and_frames_in_order() {
+        let mut palette: Palette = [(0, 0, 0); 16];
+        for (index, colour) in palette.iter_mut().enumerate() {
+            *colour = (index as u8, 100 + index as u8, 200 + index as u8);
+        }
+        let pixels = noise(300 * 200);
+
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut encoder = GifEncoder::new(&mut bytes, 300, 200, &palette).unwrap();
+        encoder.add_frame(&pixels, 7).unwrap();
+        encoder.finish().unwrap();
+
+        assert_eq!(&bytes[..6], b"GIF89a");
+        assert_eq!(&bytes[6..10], &[44, 1, 200, 0]);
+        assert_eq!(&bytes[10..13], &[0b1111_0011, 0, 0]);
+        let palette_bytes: Vec<u8> = palette.iter().flat_map(|(red, green, blue)| [*red, *green, *blue]).collect();
+        assert_eq!(&bytes[13..61], palette_bytes.as_slice());
+        assert_eq!(&bytes[61..64], &[0x21, 0xff, 0x0b]);
+        assert_eq!(&bytes[64..75], b"NETSCAPE2.0");
+        assert_eq!(&bytes[75..80], &[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        // Graphic control extension with the delay, then the image descriptor
+        assert_eq!(&bytes[80..88], &[0x21, 0xf9, 0x04, 0b0000_0100, 7, 0, 0, 0]);
+        assert_eq!(&bytes[88..98], &[0x2c, 0, 0, 0, 0, 44, 1, 200, 0, 0]);
+        assert_eq!(bytes[98], MIN_CODE_SIZE);
+
+        let mut data: Vec<u8> = Vec::new();
+        let mut position = 99;
+        while bytes[position] != 0 {
+            let length = bytes[position] as usize;
+            data.extend(&bytes[position + 1..position + 1 + length]);
+            position += 1 + length;
+        }
+        assert_eq!(lzw_decode(&data).pixels, pixels);
+        assert_eq!(&bytes[position + 1..], &[0x3b]);
+    }
+}
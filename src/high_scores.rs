@@ -0,0 +1,100 @@
+// The best games of each mode, kept in a file next to the replays. Sprints are ranked by time,
+// everything else by score.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+const MAX_HIGH_SCORES_PER_MODE: usize = 10;
+
+#[derive(Clone)]
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    pub lines: u32,
+    // In milliseconds
+    pub time: u64,
+    pub seed: u64,
+    // YYYY-MM-DD
+    pub date: String,
+}
+
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct HighScores {
+    // Best first, by mode name
+    #[serde(flatten)]
+    modes: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl HighScores {
+    // A missing file just means nobody has set a high score yet
+    pub fn load(path: &Path) -> Result<HighScores, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(_) if !path.exists() => return Ok(HighScores { modes: BTreeMap::new() }),
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+
+        return toml::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error.to_string().trim()));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|error| error.to_string())?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+        }
+
+        return fs::write(path, text).map_err(|error| format!("{}: {}", path.display(), error));
+    }
+
+    pub fn modes(&self) -> impl Iterator<Item = (&String, &Vec<HighScore>)> {
+        return self.modes.iter();
+    }
+
+    // Returns the place the game got, counting from 1, or None if it didn't make the table
+    pub fn add(&mut self, mode: &str, high_score: HighScore) -> Option<usize> {
+        let high_scores = self.modes.entry(mode.to_string()).or_default();
+        let is_better = |other: &HighScore| if mode == "sprint" { high_score.time < other.time } else { high_score.score > other.score };
+
+        let place = high_scores.iter().position(is_better).unwrap_or(high_scores.len());
+        if place >= MAX_HIGH_SCORES_PER_MODE {
+            return None;
+        }
+
+        high_scores.insert(place, high_score);
+        high_scores.truncate(MAX_HIGH_SCORES_PER_MODE);
+        return Some(place + 1);
+    }
+}
+
+pub fn high_scores_path() -> Option<PathBuf> {
+    return dirs::data_dir().map(|directory| directory.join("terminal-tetris").join("high_scores.toml"));
+}
+
+// Today's date in UTC, worked out by hand to save pulling in a date library
+pub fn today() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let days = (seconds / 86400) as i64;
+
+    // Howard Hinnant's days-to-civil algorithm, with eras of 400 years starting on 1 March
+    let shifted_days = days + 719468;
+    let era = shifted_days / 146097;
+    let day_of_era = shifted_days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    return format!("{:04}-{:02}-{:02}", year, month, day);
+}
+
+// Minutes, seconds and milliseconds, e.g. 1:23.456
+pub fn format_time(milliseconds: u64) -> String {
+    return format!("{}:{:02}.{:03}", milliseconds / 60000, milliseconds / 1000 % 60, milliseconds % 1000);
+}
@@ -0,0 +1,103 @@
+// Every finished game's stats, one after another in a file next to the high scores, for
+// charting how play changes over time. Each game is its own [[game]] table, so finishing a
+// game only has to add to the end of the file.
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::stats::{GameStats, CLEAR_KINDS, PIECE_NAMES};
+
+#[derive(Clone)]
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct HistoryEntry {
+    // YYYY-MM-DD
+    pub date: String,
+    pub mode: String,
+    // Whose game it was
+    pub profile: String,
+    pub seed: u64,
+    pub score: u32,
+    pub lines: u32,
+    // In milliseconds
+    pub time: u64,
+    pub pieces: u32,
+    pub pieces_per_second: f64,
+    pub keys_per_piece: f64,
+    pub attack_per_minute: f64,
+    pub attack: u32,
+    pub key_presses: u32,
+    pub holds: u32,
+    pub finesse_faults: u32,
+    pub max_combo: u32,
+    pub max_back_to_back: u32,
+    pub perfect_clears: u32,
+    // By piece name
+    pub pieces_by_type: BTreeMap<String, u32>,
+    // By clear kind, e.g. "t-spin-double". Kinds that never happened are left out.
+    pub clears: BTreeMap<String, u32>,
+}
+
+#[derive(Serialize)]
+#[derive(Deserialize)]
+struct History {
+    #[serde(default)]
+    game: Vec<HistoryEntry>,
+}
+
+impl HistoryEntry {
+    pub fn new(mode: &str, profile: &str, seed: u64, date: String, stats: &GameStats) -> HistoryEntry {
+        let pieces_by_type = PIECE_NAMES.iter().zip(stats.pieces_by_type).map(|(name, count)| (name.to_string(), count)).collect();
+        let clears = CLEAR_KINDS
+            .iter()
+            .zip(stats.clears)
+            .filter(|(_, count)| *count > 0)
+            .map(|((_, _, name), count)| (name.to_lowercase().replace(' ', "-"), count))
+            .collect();
+
+        return HistoryEntry {
+            date,
+            mode: mode.to_string(),
+            profile: profile.to_string(),
+            seed,
+            score: stats.score,
+            lines: stats.lines,
+            time: (stats.time / 1000) as u64,
+            pieces: stats.pieces,
+            pieces_per_second: round(stats.pieces_per_second()),
+            keys_per_piece: round(stats.keys_per_piece()),
+            attack_per_minute: round(stats.attack_per_minute()),
+            attack: stats.attack,
+            key_presses: stats.key_presses,
+            holds: stats.holds,
+            finesse_faults: stats.finesse_faults,
+            max_combo: stats.max_combo,
+            max_back_to_back: stats.max_back_to_back,
+            perfect_clears: stats.perfect_clears,
+            pieces_by_type,
+            clears,
+        }
+    }
+}
+
+pub fn append_history(path: &Path, entry: HistoryEntry) -> Result<(), String> {
+    let text = toml::to_string(&History { game: Vec::from([entry]) }).map_err(|error| error.to_string())?;
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    return writeln!(file, "{}", text).map_err(|error| format!("{}: {}", path.display(), error));
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    return dirs::data_dir().map(|directory| directory.join("terminal-tetris").join("history.toml"));
+}
+
+// Two decimal places are plenty for rates, and keep the file readable
+fn round(value: f64) -> f64 {
+    return (value * 100.0).round() / 100.0;
+}
@@ -0,0 +1,161 @@
+use tetris_core::tetris::{LockResult, TSpin, Tetris};
+
+// Times are in microseconds
+const ACTION_TEXT_DURATION: u128 = 1500000;
+// How long before the end the action text switches to its faded colour
+const ACTION_TEXT_FADE_TIME: u128 = 500000;
+
+const LINES_PER_LEVEL: u32 = 10;
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum ActionTextFade {
+    Bright,
+    Faded,
+}
+
+// The score and running numbers shown next to a board
+pub struct Hud {
+    pub score: u32,
+    pub lines: u32,
+    pub pieces: u32,
+    pub time: u128,
+    action_text: Vec<&'static str>,
+    action_text_age: u128,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        return Hud {
+            score: 0,
+            lines: 0,
+            pieces: 0,
+            time: 0,
+            action_text: Vec::new(),
+            action_text_age: 0,
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        return 1 + self.lines / LINES_PER_LEVEL;
+    }
+
+    pub fn pieces_per_second(&self) -> f64 {
+        if self.time == 0 {
+            return 0.0;
+        }
+
+        return self.pieces as f64 / (self.time as f64 / 1000000.0);
+    }
+
+    pub fn action_text(&self) -> Option<(&[&'static str], ActionTextFade)> {
+        if self.action_text.is_empty() {
+            return None;
+        }
+
+        let fade = if self.action_text_age + ACTION_TEXT_FADE_TIME < ACTION_TEXT_DURATION {
+            ActionTextFade::Bright
+        } else {
+            ActionTextFade::Faded
+        };
+
+        return Some((&self.action_text, fade));
+    }
+
+    // Returns whether the action text changed, so the board needs redrawing
+    pub fn update(&mut self, delta_time: &u128) -> bool {
+        self.time += delta_time;
+
+        if self.action_text.is_empty() {
+            return false;
+        }
+
+        let fade_before = self.action_text().map(|(_, fade)| fade);
+        self.action_text_age += delta_time;
+
+        if self.action_text_age >= ACTION_TEXT_DURATION {
+            self.action_text.clear();
+            return true;
+        }
+
+        return self.action_text().map(|(_, fade)| fade) != fade_before;
+    }
+
+    // Catches up with any piece that locked in `tetris` since the last call
+    pub fn observe(&mut self, tetris: &Tetris) {
+        if tetris.pieces_locked() == self.pieces {
+            return;
+        }
+
+        self.pieces = tetris.pieces_locked();
+
+        if let Some(lock_result) = tetris.last_lock_result() {
+            self.on_piece_locked(lock_result);
+        }
+    }
+
+    fn on_piece_locked(&mut self, lock_result: &LockResult) {
+        let level = self.level();
+        let mut action_text: Vec<&'static str> = Vec::new();
+
+        let mut clear_score = match (lock_result.t_spin, lock_result.cleared_lines) {
+            (TSpin::None, 1) => 100,
+            (TSpin::None, 2) => 300,
+            (TSpin::None, 3) => 500,
+            (TSpin::None, 4) => 800,
+            (TSpin::Mini, 0) => 100,
+            (TSpin::Mini, 1) => 200,
+            (TSpin::Mini, _) => 400,
+            (TSpin::Full, 0) => 400,
+            (TSpin::Full, 1) => 800,
+            (TSpin::Full, 2) => 1200,
+            (TSpin::Full, _) => 1600,
+            _                => 0,
+        };
+
+        if let Some(text) = clear_text(lock_result) {
+            action_text.push(text);
+        }
+
+        if lock_result.is_back_to_back() {
+            clear_score = clear_score * 3 / 2;
+            action_text.push("BACK-TO-BACK");
+        }
+
+        self.score += clear_score * level;
+        self.score += 50 * lock_result.combo.unwrap_or(0) * level;
+
+        if lock_result.is_perfect_clear {
+            let perfect_clear_score = match lock_result.cleared_lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ => 2000,
+            };
+            self.score += perfect_clear_score * level;
+            action_text.push("PERFECT CLEAR");
+        }
+
+        self.lines += lock_result.cleared_lines as u32;
+
+        if !action_text.is_empty() {
+            self.action_text = action_text;
+            self.action_text_age = 0;
+        }
+    }
+}
+
+fn clear_text(lock_result: &LockResult) -> Option<&'static str> {
+    return match (lock_result.t_spin, lock_result.cleared_lines) {
+        (TSpin::None, 4) => Some("TETRIS"),
+        (TSpin::Mini, 0) => Some("T-SPIN MINI"),
+        (TSpin::Mini, 1) => Some("T-SPIN MINI SINGLE"),
+        (TSpin::Mini, _) => Some("T-SPIN MINI DOUBLE"),
+        (TSpin::Full, 0) => Some("T-SPIN"),
+        (TSpin::Full, 1) => Some("T-SPIN SINGLE"),
+        (TSpin::Full, 2) => Some("T-SPIN DOUBLE"),
+        (TSpin::Full, _) => Some("T-SPIN TRIPLE"),
+        _                => None,
+    }
+}
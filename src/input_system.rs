@@ -1,41 +1,62 @@
 use device_query::{DeviceState, Keycode};
 use device_query::DeviceQuery;
-use crate::core::Key;
+use tetris_core::tetris::MAX_BOARD_WIDTH;
+use crate::game::Key;
+use crate::settings::{Settings, GAME_KEYS};
+
+// Keys that auto-repeat while held
+const REPEATING_KEYS: [Key; 3] = [Key::MoveLeft, Key::MoveRight, Key::SoftDrop];
 
 pub struct InputSystem {
-    device_state: DeviceState,
+    // None when nobody's at the keyboard, e.g. for a bot game recorded without a terminal
+    device_state: Option<DeviceState>,
     last_frame_keys: Vec<Keycode>,
     current_frame_keys: Vec<Keycode>,
-    key_repeat_interval: u128,
-    time_since_last_left: u128,
-    time_since_last_right: u128,
-    time_since_last_down: u128,
+    repeated_keys: Vec<Key>,
+    // Microseconds each of REPEATING_KEYS has been held for
+    held_times: [u128; 3],
 }
 
 impl InputSystem {
-    pub fn new(key_repeat_interval: u128) -> InputSystem {
-        let device_state = DeviceState::new();
+    pub fn new() -> InputSystem {
+        return InputSystem::with_device_state(Some(DeviceState::new()));
+    }
+
+    pub fn without_keyboard() -> InputSystem {
+        return InputSystem::with_device_state(None);
+    }
+
+    fn with_device_state(device_state: Option<DeviceState>) -> InputSystem {
         return InputSystem {
             device_state,
-            key_repeat_interval,
             last_frame_keys: Vec::new(),
             current_frame_keys: Vec::new(),
-            time_since_last_left: 0,
-            time_since_last_right: 0,
-            time_since_last_down: 0,
+            repeated_keys: Vec::new(),
+            held_times: [0; 3],
         }
     }
 
-    pub fn get_keys(&mut self, delta_time: &u128) -> Vec<Key> {
+    // Moving sideways waits out the DAS before repeating every ARR. Soft drop repeats every
+    // `soft_drop_interval` microseconds from the start.
+    pub fn get_keys(&mut self, delta_time: &u128, settings: &Settings, soft_drop_interval: u128) -> Vec<Key> {
         let mut keys: Vec<Key> = Vec::new();
-        self.current_frame_keys = self.device_state.get_keys();
-
-        if self.is_key_pressed(&Keycode::P) {
-            keys.push(Key::P);
-        }
-
-        if self.is_key_pressed(&Keycode::Escape) {
-            keys.push(Key::Escape);
+        self.repeated_keys.clear();
+        self.current_frame_keys = self.device_state.as_ref().map_or(Vec::new(), |device_state| device_state.get_keys());
+
+        // Menus always use the same keys, whatever the controls are
+        let menu_keys = [
+            (Keycode::Up,     Key::Up),
+            (Keycode::Down,   Key::Down),
+            (Keycode::Left,   Key::Left),
+            (Keycode::Right,  Key::Right),
+            (Keycode::Enter,  Key::Enter),
+            (Keycode::Escape, Key::Escape),
+        ];
+
+        for (keycode, key) in menu_keys {
+            if self.is_key_pressed(&keycode) {
+                keys.push(key);
+            }
         }
 
         if self.current_frame_keys.contains(&Keycode::LControl) {
@@ -46,70 +67,56 @@ impl InputSystem {
             keys.push(Key::C);
         }
 
-        if self.current_frame_keys.contains(&Keycode::Left) && !self.current_frame_keys.contains(&Keycode::Right) {
-            if self.last_frame_keys.contains(&Keycode::Left) {
-                self.time_since_last_left += delta_time;
-                if self.time_since_last_left > self.key_repeat_interval {
-                    self.time_since_last_left -= self.key_repeat_interval;
-                    keys.push(Key::Left);
-                }
-            } else {
-                keys.push(Key::Left);
-            }
-        } else {
-            self.time_since_last_left = 0;
-        }
+        let bindings = &settings.controls.bindings;
+        let held: Vec<bool> = bindings.iter().map(|keycodes| keycodes.iter().any(|keycode| self.current_frame_keys.contains(keycode))).collect();
+        let pressed: Vec<bool> = bindings.iter().map(|keycodes| keycodes.iter().any(|keycode| self.is_key_pressed(keycode))).collect();
 
-        if self.current_frame_keys.contains(&Keycode::Right) && !self.current_frame_keys.contains(&Keycode::Left) {
-            if self.last_frame_keys.contains(&Keycode::Right) {
-                self.time_since_last_right += delta_time;
-                if self.time_since_last_right > self.key_repeat_interval {
-                    self.time_since_last_right -= self.key_repeat_interval;
-                    keys.push(Key::Right);
-                }
-            } else {
-                keys.push(Key::Right);
-            }
-        } else {
-            self.time_since_last_right = 0;
-        }
+        // Holding both directions at once moves neither way
+        let both_directions_held = held[game_key_index(&Key::MoveLeft)] && held[game_key_index(&Key::MoveRight)];
 
-        if self.current_frame_keys.contains(&Keycode::Down) {
-            if self.last_frame_keys.contains(&Keycode::Down) {
-                self.time_since_last_down += delta_time;
-                if self.time_since_last_down > self.key_repeat_interval {
-                    self.time_since_last_down -= self.key_repeat_interval;
-                    keys.push(Key::Down);
+        for (index, (key, _, _)) in GAME_KEYS.iter().enumerate() {
+            let repeat_index = REPEATING_KEYS.iter().position(|repeating_key| repeating_key == key);
+
+            let repeat_index = match repeat_index {
+                Some(repeat_index) => repeat_index,
+                None => {
+                    if pressed[index] {
+                        keys.push(key.clone());
+                    }
+                    continue;
                 }
-            } else {
-                keys.push(Key::Down);
-            }
-        } else {
-            self.time_since_last_down = 0;
-        }
+            };
 
-        if self.is_key_pressed(&Keycode::Z) {
-            keys.push(Key::Z);
-        }
+            let is_sideways = *key != Key::SoftDrop;
+            if !held[index] || (is_sideways && both_directions_held) {
+                self.held_times[repeat_index] = 0;
+                continue;
+            }
 
-        if self.is_key_pressed(&Keycode::Up) {
-            keys.push(Key::Up);
-        }
+            if pressed[index] {
+                self.held_times[repeat_index] = 0;
+                keys.push(key.clone());
+                continue;
+            }
 
-        if self.is_key_pressed(&Keycode::X) {
-            keys.push(Key::X);
-        }
+            let (delay, interval) = if is_sideways {
+                (settings.das * 1000, settings.arr * 1000)
+            } else {
+                (soft_drop_interval, soft_drop_interval)
+            };
 
-        if self.is_key_pressed(&Keycode::Space) {
-            keys.push(Key::Space);
-        }
+            let held_before = self.held_times[repeat_index];
+            self.held_times[repeat_index] += delta_time;
 
-        if self.is_key_pressed(&Keycode::Enter) {
-            keys.push(Key::Enter);
+            for _ in 0..repeats_between(held_before, self.held_times[repeat_index], delay, interval) {
+                keys.push(key.clone());
+                self.repeated_keys.push(key.clone());
+            }
         }
 
-        if self.is_key_pressed(&Keycode::LShift) {
-            keys.push(Key::Shift);
+        // Anything pressed this frame, for binding controls
+        for keycode in self.current_frame_keys.iter().filter(|keycode| !self.last_frame_keys.contains(keycode)) {
+            keys.push(Key::Pressed(*keycode));
         }
 
         self.last_frame_keys = self.current_frame_keys.clone();
@@ -117,7 +124,28 @@ impl InputSystem {
         return keys;
     }
 
+    pub fn repeated_keys(&self) -> &[Key] {
+        return &self.repeated_keys;
+    }
+
     fn is_key_pressed(&self, key: &Keycode) -> bool {
         return self.current_frame_keys.contains(key) && !self.last_frame_keys.contains(key);
     }
 }
+
+fn game_key_index(key: &Key) -> usize {
+    return GAME_KEYS.iter().position(|(game_key, _, _)| game_key == key).unwrap();
+}
+
+// How many auto-repeats fall due as a key goes from being held `held_before` microseconds to
+// `held_after`. An interval of zero repeats all the way to the wall at once, and no board is
+// wider than that.
+fn repeats_between(held_before: u128, held_after: u128, delay: u128, interval: u128) -> usize {
+    if interval == 0 {
+        return if held_after >= delay { MAX_BOARD_WIDTH } else { 0 };
+    }
+
+    let repeats_by = |held_time: u128| if held_time < delay { 0 } else { (held_time - delay) / interval + 1 };
+
+    return (repeats_by(held_after) - repeats_by(held_before)) as usize;
+}
@@ -0,0 +1,174 @@
+// Works out where each panel goes for the current terminal size.
+// Sizes are in terminal cells, and every block is two columns wide.
+
+// Every panel has a one cell border all the way round, with its title set into the top edge
+const BORDER: u16 = 1;
+// Columns between neighbouring panels
+const PANEL_GAP: u16 = 1;
+// Columns between the two playfields in Versus mode
+const PLAYFIELD_GAP: u16 = 4;
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Rect {
+    pub column: u16,
+    pub row: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    fn new(column: u16, row: u16, (width, height): (u16, u16)) -> Rect {
+        return Rect { column, row, width, height };
+    }
+}
+
+// How big the inside of each panel of a playfield is, before it's placed
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct PanelSizes {
+    pub board: (u16, u16),
+    pub hold: (u16, u16),
+    pub next: (u16, u16),
+    pub hud: (u16, u16),
+    pub info_height: u16,
+}
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+enum Panel {
+    Hold,
+    Next,
+    Hud,
+}
+
+// Which panels go in the columns either side of the board, top to bottom.
+// Tried in order until one fits, so the roomiest come first.
+const ARRANGEMENTS: [(&[Panel], &[Panel]); 3] = [
+    (&[Panel::Hold, Panel::Hud], &[Panel::Next]),
+    (&[], &[Panel::Next, Panel::Hold, Panel::Hud]),
+    // Not even room for the HUD
+    (&[], &[Panel::Next, Panel::Hold]),
+];
+
+// Every rect is the inside of the panel, where its blocks or text go.
+// Borders and titles go just outside.
+pub struct PlayfieldLayout {
+    pub board: Rect,
+    pub hold: Rect,
+    // None when nothing is previewed
+    pub next: Option<Rect>,
+    pub hud: Option<Rect>,
+    pub info: Rect,
+}
+
+impl PanelSizes {
+    fn outer_size(&self, panel: Panel) -> (u16, u16) {
+        let (width, height) = match panel {
+            Panel::Hold => self.hold,
+            Panel::Next => self.next,
+            Panel::Hud  => self.hud,
+        };
+
+        // Empty panels are left out altogether, border and all
+        if width == 0 || height == 0 {
+            return (0, 0);
+        }
+
+        return (width + 2 * BORDER, height + 2 * BORDER);
+    }
+
+    fn column_size(&self, panels: &[Panel]) -> (u16, u16) {
+        let width = panels.iter().map(|panel| self.outer_size(*panel).0).max().unwrap_or(0);
+        let height = panels.iter().map(|panel| self.outer_size(*panel).1).sum();
+        return (width, height);
+    }
+
+    fn size(&self, (left, right): (&[Panel], &[Panel])) -> (u16, u16) {
+        let (board_width, board_height) = (self.board.0 + 2 * BORDER, self.board.1 + 2 * BORDER);
+        let (left_width, left_height) = self.column_size(left);
+        let (right_width, right_height) = self.column_size(right);
+
+        let gaps = if left.is_empty() { 0 } else { PANEL_GAP } + if right.is_empty() { 0 } else { PANEL_GAP };
+
+        return (
+            left_width + right_width + board_width + gaps,
+            board_height.max(left_height).max(right_height) + self.info_height,
+        );
+    }
+
+    fn place(&self, arrangement: (&[Panel], &[Panel]), column: u16, row: u16) -> PlayfieldLayout {
+        let (left, right) = arrangement;
+        let (width, height) = self.size(arrangement);
+        let (left_width, _) = self.column_size(left);
+
+        let board_column = if left.is_empty() { column } else { column + left_width + PANEL_GAP };
+        let right_column = board_column + self.board.0 + 2 * BORDER + PANEL_GAP;
+
+        let mut layout = PlayfieldLayout {
+            board: Rect::new(board_column + BORDER, row + BORDER, self.board),
+            hold: Rect::new(0, 0, (0, 0)),
+            next: None,
+            hud: None,
+            info: Rect::new(column, row + height - self.info_height, (width, self.info_height)),
+        };
+
+        for (panels, panels_column) in [(left, column), (right, right_column)] {
+            let mut panel_row = row;
+            for panel in panels {
+                let (_, outer_height) = self.outer_size(*panel);
+                if outer_height == 0 {
+                    continue;
+                }
+
+                let inner_column = panels_column + BORDER;
+                let inner_row = panel_row + BORDER;
+
+                match panel {
+                    Panel::Hold => layout.hold = Rect::new(inner_column, inner_row, self.hold),
+                    Panel::Next => layout.next = Some(Rect::new(inner_column, inner_row, self.next)),
+                    Panel::Hud  => layout.hud = Some(Rect::new(inner_column, inner_row, self.hud)),
+                }
+
+                panel_row += outer_height;
+            }
+        }
+
+        return layout;
+    }
+}
+
+// Lays out `playfield_count` identical playfields side by side, centred in the terminal.
+// Uses the roomiest arrangement that fits, or returns the terminal sizes that would work.
+pub fn layout_playfields(terminal_size: (u16, u16), sizes: &PanelSizes, playfield_count: u16) -> Result<Vec<PlayfieldLayout>, Vec<(u16, u16)>> {
+    let (terminal_width, terminal_height) = terminal_size;
+    let total_size = |arrangement: (&[Panel], &[Panel])| {
+        let (width, height) = sizes.size(arrangement);
+        return (playfield_count * width + (playfield_count - 1) * PLAYFIELD_GAP, height);
+    };
+
+    let arrangement = ARRANGEMENTS
+        .into_iter()
+        .find(|arrangement| {
+            let (width, height) = total_size(*arrangement);
+            return width <= terminal_width && height <= terminal_height;
+        });
+
+    let arrangement = match arrangement {
+        Some(arrangement) => arrangement,
+        None => return Err(ARRANGEMENTS.into_iter().map(total_size).collect()),
+    };
+
+    let (total_width, total_height) = total_size(arrangement);
+    let (playfield_width, _) = sizes.size(arrangement);
+    let start_column = (terminal_width - total_width) / 2;
+    let start_row = (terminal_height - total_height) / 2;
+
+    return Ok((0..playfield_count)
+        .map(|index| sizes.place(arrangement, start_column + index * (playfield_width + PLAYFIELD_GAP), start_row))
+        .collect());
+}
@@ -1,64 +1,342 @@
 mod renderer;
 mod input_system;
-mod core;
+mod game;
+mod frame_buffer;
+mod settings;
+mod layout;
+mod hud;
+mod theme;
+mod animation;
+mod menu;
+mod cli;
+mod high_scores;
+mod replay;
+mod asciicast;
+mod gif;
+mod bitmap_font;
+mod pixel_renderer;
+mod export;
+mod stats;
+mod history;
+mod profile;
 
 use std::{thread, time};
+use std::io::{stdout, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Duration;
-use crate::core::{Game, Renderer, UpdateOutcome};
+use tetris_core::ticker::Ticker;
+use crate::asciicast::CastRecorder;
+use crate::export::export_gif;
+use crate::cli::{parse_args, CliCommand, PlayOptions, USAGE};
+use crate::game::{Game, Key, Renderer, UpdateOutcome};
+use crate::high_scores::{format_time, high_scores_path, HighScores};
 use crate::input_system::{InputSystem};
-use crate::renderer::TerminalRenderer;
-use crate::core::tetris::Tetris;
-use crate::core::tetris::MoveOutcome::{SpawnedNewPieceAndClearedLines};
-use crate::core::ticker::Ticker;
+use crate::renderer::{BlockStyle, TerminalRenderer};
+use crate::replay::{Frame, Replay};
+use crate::settings::{settings_path, Settings};
+use crate::theme::Theme;
 
 // Times are in microseconds
 const TICK_INTERVAL_TIME: u128 =       1000000;
 const DELTA_TICK_INTERVAL_TIME: u128 = 100000;
 const MIN_TICK_INTERVAL_TIME: u128 =   100000;
-const KEY_REPEAT_INTERVAL: u128 =      100000;
+const BOT_INPUT_INTERVAL: u128 =       50000;
+// How long problems with the settings file stay on screen before the game starts
+const SETTINGS_PROBLEMS_DISPLAY_TIME: Duration = Duration::from_secs(3);
 
-fn main() -> Result<(), ()> {
+fn main() -> ExitCode {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(problem) => {
+            eprintln!("{}\n\n{}", problem, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    return match command {
+        CliCommand::Play(options)          => play(options),
+        CliCommand::ListHighScores(mode)   => list_high_scores(mode),
+        CliCommand::CheckConfig(path)      => check_config(path),
+        CliCommand::Export { replay, gif } => export(&replay, &gif),
+        CliCommand::Help                   => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+fn play(options: PlayOptions) -> ExitCode {
     let mut last_frame_start_time: u128 = 0;
     let mut now: u128;
     let mut delta_time: u128;
 
-    let mut input_system = InputSystem::new(KEY_REPEAT_INTERVAL);
+    let settings_path = options.config.clone().or_else(settings_path);
+    let mut settings = match &settings_path {
+        Some(path) => {
+            let (settings, problems) = Settings::load(path);
+            if !problems.is_empty() {
+                for problem in problems {
+                    eprintln!("{}", problem);
+                }
+                eprintln!("Using the defaults for those instead.");
+                thread::sleep(SETTINGS_PROBLEMS_DISPLAY_TIME);
+            }
+            settings
+        }
+        None => Settings::new(),
+    };
+
+    if let Some(name) = &options.theme {
+        match Theme::all().into_iter().find(|theme| theme.name.eq_ignore_ascii_case(name)) {
+            Some(theme) => settings.override_theme(theme),
+            None => {
+                let theme_names: Vec<String> = Theme::all().into_iter().map(|theme| theme.name).collect();
+                eprintln!("There's no theme called '{}', try one of {}", name, theme_names.join(", "));
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if let Some(profile) = &options.profile {
+        settings.override_profile(profile.clone());
+    }
 
-    TerminalRenderer::setup();
+    if options.ascii {
+        settings.ascii = true;
+        settings.override_block_style(BlockStyle::Monochrome);
+    }
 
-    let mut renderer = TerminalRenderer::new();
-    let mut ticker: Ticker = Ticker::new(
-        TICK_INTERVAL_TIME,
-        MIN_TICK_INTERVAL_TIME,
-        DELTA_TICK_INTERVAL_TIME,
-    );
-    let mut game: Game = Game::new(&mut ticker);
+    // Replays are played with the rules they were recorded with, which mustn't end up saved
+    let (replay, mut frames) = match &options.replay {
+        Some(path) => match load_replay(path, &mut settings) {
+            Ok((replay, frames)) => (Some(replay), frames.into_iter()),
+            Err(problem) => {
+                eprintln!("{}", problem);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => (None, Vec::new().into_iter()),
+    };
+    let settings_path = if replay.is_some() { None } else { settings_path };
+
+    // Without a terminal there's nobody to play, but games that end by themselves can still be recorded
+    let has_terminal = stdout().is_terminal();
+    if !has_terminal && replay.is_none() && !(options.bot && options.no_menu) {
+        eprintln!("Without a terminal, only replays and --bot --no-menu games can be played");
+        return ExitCode::FAILURE;
+    }
+
+    let output: Box<dyn Write> = match &options.record {
+        Some(path) => match CastRecorder::create(stdout(), path) {
+            Ok(recorder) => Box::new(recorder),
+            Err(problem) => {
+                eprintln!("{}", problem);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(stdout()),
+    };
+
+    let mut input_system = if has_terminal { InputSystem::new() } else { InputSystem::without_keyboard() };
+
+    if has_terminal {
+        TerminalRenderer::setup();
+    }
+
+    let mut renderer = TerminalRenderer::new(output);
+    let mut ticker: Ticker = new_ticker();
+    let mut game: Game = Game::new(&mut ticker, BOT_INPUT_INTERVAL, settings, settings_path);
+    game.set_seed(options.seed);
+    game.set_autoplay(options.bot);
+    game.set_exit_when_finished(options.no_menu || replay.is_some());
+    game.set_record_games(replay.is_none());
+
+    match (&replay, options.mode) {
+        (Some(replay), _)         => {
+            game.set_seed(Some(replay.seed));
+            if let Ok((mode, big)) = replay.game_mode() {
+                game.start(mode, big);
+            }
+        }
+        (None, Some((mode, big))) => game.start(mode, big),
+        (None, None)              => (),
+    }
 
     let start = time::Instant::now();
     let state = game.state();
-    renderer.render(&state);
+    renderer.render(&state, game.settings());
 
     loop {
         now = start.elapsed().as_micros();
         delta_time = now - last_frame_start_time;
         last_frame_start_time = now;
-        let keys = input_system.get_keys(&delta_time);
+        let mut keys = input_system.get_keys(&delta_time, game.settings(), game.soft_drop_interval());
+        let mut repeated_keys = input_system.repeated_keys().to_vec();
 
-        let update_outcome = game.update(&keys, &delta_time);
+        // Playing a replay, the only keys that count are the ones to stop watching
+        if replay.is_some() {
+            if keys.contains(&Key::Escape) {
+                return exit(None, has_terminal);
+            }
+
+            let frame = match frames.next() {
+                Some(frame) => frame,
+                None        => return exit(game.result(), has_terminal),
+            };
+
+            let is_quitting = keys.contains(&Key::Control) && keys.contains(&Key::C);
+            keys = frame.keys;
+            if is_quitting {
+                keys.extend([Key::Control, Key::C]);
+            }
+            repeated_keys = frame.repeated_keys;
+            delta_time = frame.delta_time;
+        }
+
+        let update_outcome = game.update(&keys, &repeated_keys, &delta_time);
 
         match update_outcome {
-            UpdateOutcome::Exit => { return exit(); }
-            UpdateOutcome::Render => { renderer.render(&game.state()) }
+            UpdateOutcome::Exit => { return exit(game.result().filter(|_| options.no_menu || replay.is_some()), has_terminal); }
+            UpdateOutcome::Render => { renderer.render(&game.state(), game.settings()) }
+            UpdateOutcome::NothingSpecial if renderer.needs_redraw() => { renderer.render(&game.state(), game.settings()) }
             UpdateOutcome::NothingSpecial => {}
         }
 
+        // Replays keep to the pace they were recorded at
+        match replay {
+            Some(_) => thread::sleep(Duration::from_micros(delta_time as u64)),
+            None    => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+fn new_ticker() -> Ticker {
+    return Ticker::new(
+        TICK_INTERVAL_TIME,
+        MIN_TICK_INTERVAL_TIME,
+        DELTA_TICK_INTERVAL_TIME,
+    );
+}
+
+fn load_replay(path: &Path, settings: &mut Settings) -> Result<(Replay, Vec<Frame>), String> {
+    let replay = Replay::load(path)?;
+    let problem_in_replay = |problem: String| format!("{}: {}", path.display(), problem);
+
+    replay.game_mode().map_err(problem_in_replay)?;
+    replay.apply_to(settings).map_err(problem_in_replay)?;
+    let frames = replay.frames().map_err(problem_in_replay)?;
+
+    return Ok((replay, frames));
+}
+
+// The result is printed once the terminal is back to normal, so it stays on screen
+fn exit(result: Option<&str>, has_terminal: bool) -> ExitCode {
+    if has_terminal {
+        TerminalRenderer::teardown();
+    }
+    if let Some(result) = result {
+        println!("{}", result);
+    }
+    return ExitCode::SUCCESS;
+}
+
+fn list_high_scores(mode: Option<String>) -> ExitCode {
+    let high_scores = match high_scores_path().map(|path| HighScores::load(&path)) {
+        Some(Ok(high_scores)) => high_scores,
+        Some(Err(problem)) => {
+            eprintln!("{}", problem);
+            return ExitCode::FAILURE;
+        }
+        None => {
+            eprintln!("There's nowhere to keep high scores on this system");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut any_listed = false;
+    for (mode_name, scores) in high_scores.modes() {
+        if mode.as_ref().is_some_and(|mode| !mode.eq_ignore_ascii_case(mode_name)) {
+            continue;
+        }
+
+        println!("{}", mode_name);
+        for (place, score) in scores.iter().enumerate() {
+            println!(
+                "{:>4}. {:>8} points {:>5} lines {:>10}   seed {:<20} {}",
+                place + 1,
+                score.score,
+                score.lines,
+                format_time(score.time),
+                score.seed,
+                score.date,
+            );
+        }
+        println!();
+        any_listed = true;
+    }
 
-        thread::sleep(Duration::from_millis(10));
+    if !any_listed {
+        println!("No high scores yet");
     }
+
+    return ExitCode::SUCCESS;
 }
 
-fn exit() -> Result<(), ()> {
-    TerminalRenderer::teardown();
-    return Ok(());
+// Replays bring their own rules, so the player's settings don't come into it
+fn export(replay_path: &Path, gif_path: &Path) -> ExitCode {
+    let mut settings = Settings::new();
+    let (replay, frames) = match load_replay(replay_path, &mut settings) {
+        Ok(loaded) => loaded,
+        Err(problem) => {
+            eprintln!("{}", problem);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut ticker = new_ticker();
+    let mut game: Game = Game::new(&mut ticker, BOT_INPUT_INTERVAL, settings, None);
+    game.set_record_games(false);
+    game.set_exit_when_finished(true);
+    game.set_seed(Some(replay.seed));
+    if let Ok((mode, big)) = replay.game_mode() {
+        game.start(mode, big);
+    }
+
+    return match export_gif(&mut game, frames, gif_path) {
+        Ok(frames_written) => {
+            println!("Wrote {} frames to {}", frames_written, gif_path.display());
+            ExitCode::SUCCESS
+        }
+        Err(problem) => {
+            eprintln!("{}", problem);
+            ExitCode::FAILURE
+        }
+    }
 }
 
+fn check_config(path: Option<PathBuf>) -> ExitCode {
+    let path = match path.or_else(settings_path) {
+        Some(path) => path,
+        None => {
+            eprintln!("There's nowhere to keep settings on this system, so give the file to check");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if !path.exists() {
+        println!("{} doesn't exist, so the defaults are used", path.display());
+        return ExitCode::SUCCESS;
+    }
+
+    let (_, problems) = Settings::load(&path);
+    if problems.is_empty() {
+        println!("{} is fine", path.display());
+        return ExitCode::SUCCESS;
+    }
+
+    for problem in problems {
+        println!("{}", problem);
+    }
+    return ExitCode::FAILURE;
+}
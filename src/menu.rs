@@ -0,0 +1,223 @@
+// Menus are built at runtime out of items that either hand a command back to the game, open
+// another menu on top, or edit a setting in place. Labels are worked out from the settings
+// every frame, so they always show the current value.
+use std::ops::RangeInclusive;
+use crate::game::{Command, Key};
+use crate::settings::Settings;
+
+type Getter<T> = Box<dyn Fn(&Settings) -> T>;
+type Setter<T> = Box<dyn Fn(&mut Settings, T)>;
+
+pub struct Menu {
+    pub title: String,
+    items: Vec<MenuItem>,
+    selected_item: usize,
+}
+
+pub struct MenuItem {
+    label: Label,
+    kind: MenuItemKind,
+    is_enabled: Option<Getter<bool>>,
+}
+
+enum Label {
+    Fixed(String),
+    Dynamic(Getter<String>),
+}
+
+enum MenuItemKind {
+    Command(Command),
+    Submenu(Box<dyn Fn() -> Menu>),
+    Toggle(Getter<bool>, Setter<bool>),
+    Slider {
+        range: RangeInclusive<i32>,
+        step: i32,
+        get: Getter<i32>,
+        set: Setter<i32>,
+    },
+    Choice {
+        options: Vec<String>,
+        get: Getter<usize>,
+        set: Setter<usize>,
+    },
+    Back,
+}
+
+// What a key press in a menu needs the game to do
+pub enum MenuOutcome {
+    Nothing,
+    Command(Command),
+    Open(Menu),
+    Back,
+}
+
+// One line of a menu, ready to draw
+pub struct MenuLine {
+    pub label: String,
+    pub is_enabled: bool,
+}
+
+impl MenuItem {
+    fn new(label: &str, kind: MenuItemKind) -> MenuItem {
+        return MenuItem {
+            label: Label::Fixed(label.to_string()),
+            kind,
+            is_enabled: None,
+        }
+    }
+
+    pub fn command(label: &str, command: Command) -> MenuItem {
+        return MenuItem::new(label, MenuItemKind::Command(command));
+    }
+
+    // The menu is only built when it's opened, so it can show whatever is current by then
+    pub fn submenu(label: &str, make_menu: impl Fn() -> Menu + 'static) -> MenuItem {
+        return MenuItem::new(label, MenuItemKind::Submenu(Box::new(make_menu)));
+    }
+
+    pub fn toggle(label: &str, get: impl Fn(&Settings) -> bool + 'static, set: impl Fn(&mut Settings, bool) + 'static) -> MenuItem {
+        return MenuItem::new(label, MenuItemKind::Toggle(Box::new(get), Box::new(set)));
+    }
+
+    pub fn slider(
+        label: &str,
+        range: RangeInclusive<i32>,
+        step: i32,
+        get: impl Fn(&Settings) -> i32 + 'static,
+        set: impl Fn(&mut Settings, i32) + 'static,
+    ) -> MenuItem {
+        return MenuItem::new(label, MenuItemKind::Slider { range, step, get: Box::new(get), set: Box::new(set) });
+    }
+
+    pub fn choice(
+        label: &str,
+        options: &[&str],
+        get: impl Fn(&Settings) -> usize + 'static,
+        set: impl Fn(&mut Settings, usize) + 'static,
+    ) -> MenuItem {
+        let options = options.iter().map(|option| option.to_string()).collect();
+        return MenuItem::new(label, MenuItemKind::Choice { options, get: Box::new(get), set: Box::new(set) });
+    }
+
+    pub fn back(label: &str) -> MenuItem {
+        return MenuItem::new(label, MenuItemKind::Back);
+    }
+
+    // Replaces the whole label, value and all, with one worked out from the settings
+    pub fn with_label(mut self, label: impl Fn(&Settings) -> String + 'static) -> MenuItem {
+        self.label = Label::Dynamic(Box::new(label));
+        return self;
+    }
+
+    // Disabled items are shown but can't be selected
+    pub fn enabled_when(mut self, is_enabled: impl Fn(&Settings) -> bool + 'static) -> MenuItem {
+        self.is_enabled = Some(Box::new(is_enabled));
+        return self;
+    }
+
+    fn is_enabled(&self, settings: &Settings) -> bool {
+        return self.is_enabled.as_ref().is_none_or(|is_enabled| is_enabled(settings));
+    }
+
+    fn label(&self, settings: &Settings) -> String {
+        let name = match &self.label {
+            Label::Dynamic(label) => return label(settings),
+            Label::Fixed(name)    => name,
+        };
+
+        return match &self.kind {
+            MenuItemKind::Toggle(get, _)                => format!("{}: {}", name, if get(settings) { "On" } else { "Off" }),
+            MenuItemKind::Slider { get, .. }            => format!("{}: {}", name, get(settings)),
+            MenuItemKind::Choice { options, get, .. }   => format!("{}: {}", name, options[get(settings)]),
+            _                                           => name.clone(),
+        }
+    }
+
+    // Left and right step settings down and up, wrapping round for choices but not for sliders
+    fn adjust(&self, settings: &mut Settings, direction: i32) {
+        match &self.kind {
+            MenuItemKind::Toggle(get, set) => {
+                let value = get(settings);
+                set(settings, !value);
+            }
+            MenuItemKind::Slider { range, step, get, set } => {
+                let value = (get(settings) + direction * step).clamp(*range.start(), *range.end());
+                set(settings, value);
+            }
+            MenuItemKind::Choice { options, get, set } => {
+                let count = options.len() as i32;
+                let index = (get(settings) as i32 + direction).rem_euclid(count);
+                set(settings, index as usize);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Menu {
+    pub fn new(title: &str, items: Vec<MenuItem>) -> Menu {
+        return Menu {
+            title: title.to_string(),
+            items,
+            selected_item: 0,
+        }
+    }
+
+    pub fn selected_item(&self) -> usize {
+        return self.selected_item;
+    }
+
+    pub fn lines(&self, settings: &Settings) -> Vec<MenuLine> {
+        return self.items
+            .iter()
+            .map(|item| MenuLine { label: item.label(settings), is_enabled: item.is_enabled(settings) })
+            .collect();
+    }
+
+    pub fn handle_key(&mut self, key: &Key, settings: &mut Settings) -> MenuOutcome {
+        match key {
+            Key::Up     => self.move_selection(-1, settings),
+            Key::Down   => self.move_selection(1, settings),
+            Key::Escape => return MenuOutcome::Back,
+            _           => (),
+        }
+
+        let item = &self.items[self.selected_item];
+        if !item.is_enabled(settings) {
+            return MenuOutcome::Nothing;
+        }
+
+        match (key, &item.kind) {
+            (Key::Left, _)                                => item.adjust(settings, -1),
+            (Key::Right, _)                               => item.adjust(settings, 1),
+            (Key::Enter, MenuItemKind::Command(command))  => return MenuOutcome::Command(command.clone()),
+            (Key::Enter, MenuItemKind::Submenu(make_menu)) => {
+                let mut menu = make_menu();
+                menu.move_selection(0, settings);
+                return MenuOutcome::Open(menu);
+            }
+            (Key::Enter, MenuItemKind::Back)              => return MenuOutcome::Back,
+            (Key::Enter, _)                               => item.adjust(settings, 1),
+            _                                             => (),
+        }
+
+        return MenuOutcome::Nothing;
+    }
+
+    // Moves to the next enabled item in `direction`, wrapping round. A direction of 0 steps
+    // forwards off the current item only if it's disabled.
+    fn move_selection(&mut self, direction: i32, settings: &Settings) {
+        let count = self.items.len() as i32;
+        let step = if direction == 0 { 1 } else { direction };
+        let mut index = self.selected_item as i32 + direction;
+
+        for _ in 0..count {
+            index = index.rem_euclid(count);
+            if self.items[index as usize].is_enabled(settings) {
+                self.selected_item = index as usize;
+                return;
+            }
+            index += step;
+        }
+    }
+}
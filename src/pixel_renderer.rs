@@ -0,0 +1,239 @@
+// Draws games into images instead of the terminal, for exporting replays. Every block is a
+// coloured square, and the numbers use the bitmap font. Menus and prompts aren't drawn.
+use tetris_core::piece::BlockType;
+use tetris_core::tetris::Block;
+use crate::bitmap_font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::game::{Playfield, Renderer, RenderState};
+use crate::gif::Palette;
+use crate::hud::Hud;
+use crate::settings::Settings;
+
+// Sizes are in pixels
+const CELL_SIZE: usize = 8;
+const TEXT_SCALE: usize = 2;
+const LINE_HEIGHT: usize = (GLYPH_HEIGHT + 1) * TEXT_SCALE;
+const MARGIN: usize = 8;
+const BORDER_WIDTH: usize = 2;
+// Wide enough for seven characters, or a preview of the widest piece with room to spare
+const SIDE_PANEL_WIDTH: usize = 7 * (GLYPH_WIDTH + 1) * TEXT_SCALE;
+
+const BACKGROUND: u8 = 0;
+const EMPTY: u8 = 1;
+const BORDER: u8 = 2;
+const TEXT: u8 = 3;
+// The Z piece's red
+const GARBAGE_METER: u8 = 9;
+
+pub const PALETTE: Palette = [
+    (16, 16, 24),
+    (34, 34, 46),
+    (128, 128, 140),
+    (235, 235, 235),
+    (240, 220, 0),
+    (0, 220, 230),
+    (245, 150, 0),
+    (40, 90, 240),
+    (60, 210, 60),
+    (230, 40, 40),
+    (170, 60, 220),
+    (90, 90, 104),
+    (200, 200, 200),
+    (80, 160, 80),
+    (70, 110, 150),
+    (150, 150, 150),
+];
+
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    // Indices into PALETTE, row by row
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Image {
+        return Image { width, height, pixels: vec![BACKGROUND; width * height] };
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: u8) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + column] = color;
+            }
+        }
+    }
+
+    fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u8) {
+        for (index, character) in text.chars().enumerate() {
+            let glyph_x = x + index * (GLYPH_WIDTH + 1) * TEXT_SCALE;
+
+            for (row, bits) in glyph(character).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill(glyph_x + column * TEXT_SCALE, y + row * TEXT_SCALE, TEXT_SCALE, TEXT_SCALE, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct PixelRenderer {
+    image: Option<Image>,
+}
+
+impl Renderer for PixelRenderer {
+    fn render(&mut self, state: &RenderState, _settings: &Settings) {
+        let playfields = match state {
+            RenderState::Running(playfield) | RenderState::Training(playfield, _) => Vec::from([(playfield, "GAME")]),
+            RenderState::Versus(player_playfield, bot_playfield) => Vec::from([(player_playfield, "YOU"), (bot_playfield, "AI")]),
+            _ => return,
+        };
+
+        let sizes: Vec<(usize, usize)> = playfields.iter().map(|(playfield, _)| playfield_size(playfield)).collect();
+        let width = MARGIN + sizes.iter().map(|(width, _)| width + MARGIN).sum::<usize>();
+        let height = 2 * MARGIN + sizes.iter().map(|(_, height)| *height).max().unwrap_or(0);
+
+        let mut image = Image::new(width, height);
+        let mut x = MARGIN;
+        for ((playfield, title), (playfield_width, _)) in playfields.iter().zip(sizes) {
+            draw_playfield(&mut image, x, MARGIN, playfield, title);
+            x += playfield_width + MARGIN;
+        }
+
+        self.image = Some(image);
+    }
+
+    // Images are only drawn when asked for
+    fn needs_redraw(&self) -> bool {
+        return false;
+    }
+}
+
+impl PixelRenderer {
+    pub fn new() -> PixelRenderer {
+        return PixelRenderer { image: None };
+    }
+
+    // The last game frame drawn, if there's been one
+    pub fn image(&self) -> Option<&Image> {
+        return self.image.as_ref();
+    }
+}
+
+// The hold panel and the numbers down the left, the board, then the next pieces on the right
+fn playfield_size(playfield: &Playfield) -> (usize, usize) {
+    let board = &playfield.state.board;
+    let board_width = board.width() * CELL_SIZE + 2 * BORDER_WIDTH;
+    let board_height = (board.height() - board.buffer_height()) * CELL_SIZE + 2 * BORDER_WIDTH;
+    let next_height = playfield.state.next_pieces_board.blocks.len() * CELL_SIZE;
+
+    let width = SIDE_PANEL_WIDTH + MARGIN + board_width + MARGIN + SIDE_PANEL_WIDTH;
+    let height = LINE_HEIGHT + board_height.max(next_height).max(side_panel_height(playfield));
+    return (width, height);
+}
+
+fn side_panel_height(playfield: &Playfield) -> usize {
+    return playfield.state.held_piece_board.blocks.len() * CELL_SIZE + MARGIN + hud_lines(playfield.hud).len() * LINE_HEIGHT;
+}
+
+fn draw_playfield(image: &mut Image, x: usize, y: usize, playfield: &Playfield, title: &str) {
+    let state = &playfield.state;
+    let board = &state.board;
+    let board_x = x + SIDE_PANEL_WIDTH + MARGIN;
+    let board_y = y + LINE_HEIGHT;
+    let board_width = board.width() * CELL_SIZE;
+    let board_height = (board.height() - board.buffer_height()) * CELL_SIZE;
+
+    image.draw_text(x, y, "HOLD", TEXT);
+    draw_preview(image, x, board_y, &state.held_piece_board.blocks, &state.held_piece_board.half_block_offsets);
+
+    let hud_y = board_y + state.held_piece_board.blocks.len() * CELL_SIZE + MARGIN;
+    for (index, line) in hud_lines(playfield.hud).iter().enumerate() {
+        let color = if index % 2 == 0 { BORDER } else { TEXT };
+        image.draw_text(x, hud_y + index * LINE_HEIGHT, line, color);
+    }
+
+    image.draw_text(board_x, y, title, TEXT);
+    image.fill(board_x, board_y, board_width + 2 * BORDER_WIDTH, board_height + 2 * BORDER_WIDTH, BORDER);
+    image.fill(board_x + BORDER_WIDTH, board_y + BORDER_WIDTH, board_width, board_height, BACKGROUND);
+
+    for (row, line) in board.blocks[board.buffer_height()..].iter().enumerate() {
+        for (column, block) in line.iter().enumerate() {
+            let cell_x = board_x + BORDER_WIDTH + column * CELL_SIZE;
+            let cell_y = board_y + BORDER_WIDTH + row * CELL_SIZE;
+            draw_cell(image, cell_x, cell_y, &block.block_type);
+        }
+    }
+
+    // Incoming garbage fills the left wall from the floor up, a cell per line
+    let meter_height = (state.pending_garbage as usize * board.mino_size() * CELL_SIZE).min(board_height);
+    image.fill(board_x, board_y + BORDER_WIDTH + board_height - meter_height, BORDER_WIDTH, meter_height, GARBAGE_METER);
+
+    let next_x = board_x + board_width + 2 * BORDER_WIDTH + MARGIN;
+    if !state.next_pieces_board.blocks.is_empty() {
+        image.draw_text(next_x, y, "NEXT", TEXT);
+        draw_preview(image, next_x, board_y, &state.next_pieces_board.blocks, &state.next_pieces_board.half_block_offsets);
+    }
+}
+
+// Empty cells are left out, like in the terminal
+fn draw_preview(image: &mut Image, x: usize, y: usize, blocks: &[Vec<Block>], half_block_offsets: &[bool]) {
+    for (row, line) in blocks.iter().enumerate() {
+        let row_x = x + if half_block_offsets[row] { CELL_SIZE / 2 } else { 0 };
+        for (column, block) in line.iter().enumerate() {
+            if block.block_type != BlockType::Empty {
+                draw_cell(image, row_x + column * CELL_SIZE, y + row * CELL_SIZE, &block.block_type);
+            }
+        }
+    }
+}
+
+// Each cell leaves a pixel of gap on its right and bottom, so the grid shows
+// Outlined blocks are hollow, so they can't be mistaken for the ghost piece
+fn draw_cell(image: &mut Image, x: usize, y: usize, block_type: &BlockType) {
+    image.fill(x, y, CELL_SIZE - 1, CELL_SIZE - 1, color_for_block_type(block_type));
+    if *block_type == BlockType::Outline {
+        image.fill(x + 1, y + 1, CELL_SIZE - 3, CELL_SIZE - 3, BACKGROUND);
+    }
+}
+
+fn hud_lines(hud: &Hud) -> [String; 8] {
+    return [
+        String::from("SCORE"),
+        hud.score.to_string(),
+        String::from("LINES"),
+        hud.lines.to_string(),
+        String::from("LEVEL"),
+        hud.level().to_string(),
+        String::from("TIME"),
+        format_time(hud.time),
+    ];
+}
+
+// Minutes, seconds and tenths, which is as much as fits in the side panel
+fn format_time(time: u128) -> String {
+    let tenths = time / 100000;
+    return format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10);
+}
+
+fn color_for_block_type(block_type: &BlockType) -> u8 {
+    return match block_type {
+        BlockType::Empty =>           EMPTY,
+        BlockType::O =>               4,
+        BlockType::I =>               5,
+        BlockType::L =>               6,
+        BlockType::J =>               7,
+        BlockType::S =>               8,
+        BlockType::Z =>               9,
+        BlockType::T =>               10,
+        BlockType::Garbage =>         15,
+        BlockType::Ghost =>           11,
+        BlockType::Outline =>         12,
+        BlockType::Target =>          12,
+        BlockType::Hint =>            13,
+        BlockType::AlternativeHint => 14,
+    }
+}
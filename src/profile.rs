@@ -0,0 +1,171 @@
+// Each person sharing the game keeps their own totals, personal bests and recent results, in a
+// file of their own named after them. The settings say whose profile is in use.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const MAX_PROFILE_NAME_LENGTH: usize = 16;
+// Enough for the charts on the profile screen
+const MAX_RECENT_GAMES_PER_MODE: usize = 20;
+
+#[derive(Clone)]
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct ProfileGame {
+    pub score: u32,
+    pub lines: u32,
+    // In milliseconds
+    pub time: u64,
+    // YYYY-MM-DD
+    pub date: String,
+}
+
+// The finesse trainer's results over every session, so it can pick up where it left off
+#[derive(Default)]
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct TrainerResults {
+    pub pieces: u32,
+    pub clean_pieces: u32,
+    // Whether each of the latest pieces was clean, oldest first
+    pub recent_pieces: Vec<bool>,
+}
+
+impl TrainerResults {
+    // Both from 0 to 1, like the trainer's own
+    pub fn overall_accuracy(&self) -> Option<f64> {
+        return (self.pieces > 0).then(|| self.clean_pieces as f64 / self.pieces as f64);
+    }
+
+    pub fn recent_accuracy(&self) -> Option<f64> {
+        let clean_pieces = self.recent_pieces.iter().filter(|is_clean| **is_clean).count();
+        return (!self.recent_pieces.is_empty()).then(|| clean_pieces as f64 / self.recent_pieces.len() as f64);
+    }
+}
+
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct Profile {
+    pub games_played: u32,
+    // In milliseconds
+    pub time_played: u64,
+    pub lines_cleared: u64,
+    pub finesse_trainer: TrainerResults,
+    // By mode name, oldest first. Each one beat the one before it.
+    #[serde(default)]
+    personal_bests: BTreeMap<String, Vec<ProfileGame>>,
+    // By mode name, oldest first
+    #[serde(default)]
+    recent_games: BTreeMap<String, Vec<ProfileGame>>,
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        return Profile {
+            games_played: 0,
+            time_played: 0,
+            lines_cleared: 0,
+            finesse_trainer: TrainerResults::default(),
+            personal_bests: BTreeMap::new(),
+            recent_games: BTreeMap::new(),
+        }
+    }
+
+    // A missing file just means a profile that hasn't finished a game yet
+    pub fn load(path: &Path) -> Result<Profile, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(_) if !path.exists() => return Ok(Profile::new()),
+            Err(error) => return Err(format!("{}: {}", path.display(), error)),
+        };
+
+        return toml::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error.to_string().trim()));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|error| error.to_string())?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+        }
+
+        return fs::write(path, text).map_err(|error| format!("{}: {}", path.display(), error));
+    }
+
+    // Every finished game adds to the totals. Only ranked games, marathons and completed sprints,
+    // go into the recent results and can be personal bests. Returns whether it was one.
+    pub fn add_game(&mut self, mode: &str, game: ProfileGame, is_ranked: bool) -> bool {
+        self.games_played += 1;
+        self.time_played += game.time;
+        self.lines_cleared += game.lines as u64;
+
+        if !is_ranked {
+            return false;
+        }
+
+        let recent_games = self.recent_games.entry(mode.to_string()).or_default();
+        recent_games.push(game.clone());
+        if recent_games.len() > MAX_RECENT_GAMES_PER_MODE {
+            recent_games.remove(0);
+        }
+
+        let personal_bests = self.personal_bests.entry(mode.to_string()).or_default();
+        let is_personal_best = personal_bests.last().is_none_or(|best| is_better(mode, &game, best));
+        if is_personal_best {
+            personal_bests.push(game);
+        }
+
+        return is_personal_best;
+    }
+
+    pub fn personal_bests(&self) -> impl Iterator<Item = (&String, &Vec<ProfileGame>)> {
+        return self.personal_bests.iter();
+    }
+
+    pub fn recent_games(&self, mode: &str) -> &[ProfileGame] {
+        return self.recent_games.get(mode).map_or(&[], |games| games.as_slice());
+    }
+}
+
+// Sprints are ranked by time, everything else by score, like the high scores
+fn is_better(mode: &str, game: &ProfileGame, other: &ProfileGame) -> bool {
+    return if mode == "sprint" { game.time < other.time } else { game.score > other.score };
+}
+
+// Names become file names, so they're kept to lower case letters, digits and dashes
+pub fn is_valid_profile_name(name: &str) -> bool {
+    return !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LENGTH
+        && name.chars().all(|character| character.is_ascii_lowercase() || character.is_ascii_digit() || character == '-');
+}
+
+fn profiles_directory() -> Option<PathBuf> {
+    return dirs::data_dir().map(|directory| directory.join("terminal-tetris").join("profiles"));
+}
+
+pub fn profile_path(name: &str) -> Option<PathBuf> {
+    return profiles_directory().map(|directory| directory.join(format!("{}.toml", name)));
+}
+
+// Every profile that has a file, plus the default one even if it hasn't got one yet, in name order
+pub fn profile_names() -> Vec<String> {
+    let mut names: Vec<String> = profiles_directory()
+        .and_then(|directory| fs::read_dir(directory).ok())
+        .map_or(Vec::new(), |entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+                .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(|stem| stem.to_string()))
+                .filter(|name| is_valid_profile_name(name))
+                .collect()
+        });
+
+    if !names.iter().any(|name| name == DEFAULT_PROFILE) {
+        names.push(DEFAULT_PROFILE.to_string());
+    }
+
+    names.sort();
+    return names;
+}
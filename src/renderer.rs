@@ -1,152 +1,618 @@
-use std::io::{Read, stderr, Stdin, stdout, Stdout};
+use std::fmt;
+use std::io::{Read, Stdin, stdout};
 use std::io::stdin;
 use std::io::Write;
-use crate::core::tetris::TetrisState;
+use tetris_core::tetris::{Block, TetrisState};
+use tetris_core::piece::BlockType;
+use tetris_core::finesse::{finesse_cost, FinesseTrainer, RECENT_PIECES_WINDOW};
+use tetris_core::tetris::Input;
 
-use crossterm::execute;
+use crossterm::{Command, queue};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
 use crossterm::terminal::{Clear, ClearType};
-use crossterm::cursor::{MoveToColumn, MoveToRow, MoveDown};
-use crate::core::{Renderer, RenderState};
-use crate::core::tetris::BlockType;
+use crossterm::cursor::{MoveTo, MoveToColumn, MoveToRow};
+use crate::animation::{CellEffect, PlayfieldAnimations};
+use crate::frame_buffer::{Cell, FrameBuffer, Style};
+use crate::game::{Playfield, Renderer, RenderState, ThemePicker};
+use crate::hud::{ActionTextFade, Hud};
+use crate::layout::{layout_playfields, PanelSizes, PlayfieldLayout, Rect};
+use crate::menu::MenuLine;
+use crate::profile::{Profile, ProfileGame};
+use crate::high_scores::format_time;
+use crate::settings::Settings;
+use crate::stats::{GameStats, CLEAR_KINDS, PIECE_NAMES};
+use crate::theme::ThemeColor;
+
+// Rows under the board for the trainer's score and verdict
+const TRAINER_INFO_HEIGHT: u16 = 2;
+
+// Rows of the hidden buffer drawn above the board, which only show their bottom half
+const PARTIAL_ROWS: u16 = 1;
+const PARTIAL_BLOCK_GLYPH: &str = "▄▄";
+const ASCII_PARTIAL_BLOCK_GLYPH: &str = "__";
+// Incoming garbage is shown in the board's left wall
+const GARBAGE_METER_GLYPH: &str = "█";
+const ASCII_GARBAGE_METER_GLYPH: &str = "#";
+const GARBAGE_METER_COLOR: Color = Color::Red;
+
+// Wide enough for the longest action text, "T-SPIN MINI DOUBLE"
+const HUD_WIDTH: u16 = 18;
+const HUD_STATS_HEIGHT: u16 = 7;
+const HUD_ACTION_TEXT_HEIGHT: u16 = 3;
+const THEME_PREVIEW_START_COLUMN: u16 = 26;
+// Space between the columns of the results
+const RESULTS_COLUMN_GAP: usize = 4;
+// Charts of recent games get a column per game and eighths of a row of resolution
+const CHART_HEIGHT: u16 = 6;
+const CHART_COLUMN_WIDTH: u16 = 36;
+const BAR_GLYPHS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+// Used when the terminal can't tell us its size
+const FALLBACK_TERMINAL_SIZE: (u16, u16) = (80, 24);
+
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum BlockStyle {
+    Emoji,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+    Monochrome,
+}
+
+impl BlockStyle {
+    // Emoji squares look best but are double width or missing in some terminals and fonts,
+    // so fall back to plain characters where we know (or suspect) they won't work.
+    // TETRIS_BLOCK_STYLE overrides the guess.
+    pub fn detect() -> BlockStyle {
+        if let Some(block_style) = std::env::var("TETRIS_BLOCK_STYLE").ok().and_then(|name| BlockStyle::from_name(&name)) {
+            return block_style;
+        }
+
+        let term = std::env::var("TERM").unwrap_or_default();
+        let color_term = std::env::var("COLORTERM").unwrap_or_default();
+
+        if std::env::var_os("NO_COLOR").is_some() || term == "dumb" {
+            return BlockStyle::Monochrome;
+        }
+
+        if term == "linux" {
+            return BlockStyle::Ansi16;
+        }
+
+        if std::env::var_os("TMUX").is_some() || term.starts_with("screen") {
+            if color_term == "truecolor" || color_term == "24bit" {
+                return BlockStyle::TrueColor;
+            }
+
+            if term.contains("256color") {
+                return BlockStyle::Ansi256;
+            }
+
+            return BlockStyle::Ansi16;
+        }
+
+        return BlockStyle::Emoji;
+    }
+
+    pub fn from_name(name: &str) -> Option<BlockStyle> {
+        return match name.to_lowercase().as_str() {
+            "emoji"      => Some(BlockStyle::Emoji),
+            "ansi16"     => Some(BlockStyle::Ansi16),
+            "ansi256"    => Some(BlockStyle::Ansi256),
+            "truecolor"  => Some(BlockStyle::TrueColor),
+            "monochrome" => Some(BlockStyle::Monochrome),
+            _            => None,
+        }
+    }
+}
 
 pub struct TerminalRenderer {
-    stdout: Stdout,
+    // The terminal, or something passing everything on to it
+    output: Box<dyn Write>,
+    frame: FrameBuffer,
+    previous_frame: Option<FrameBuffer>,
+    // One per playfield on screen, in the order they're drawn
+    animations: [PlayfieldAnimations; 2],
 }
 
 impl Renderer for TerminalRenderer {
-    fn render(&mut self, state: &RenderState) {
+    fn render(&mut self, state: &RenderState, settings: &Settings) {
+        let (width, height) = terminal_size();
+        self.frame = FrameBuffer::new(width, height);
+
         match state {
-            RenderState::Running(tetris_state) => {
-                self.render_tetris_state(&tetris_state);
+            RenderState::Running(playfield) => {
+                if let Some(layouts) = self.layout_playfields(&playfield.state, 0, 1) {
+                    self.render_playfield(playfield, 0, "Game", &layouts[0], settings);
+                }
             }
-            RenderState::InMenu(menu) => {
-                execute!(
-                    self.stdout,
-                    Clear(ClearType::All),
-                    MoveToColumn(0),
-                    MoveToRow(0),
-                ).unwrap();
-
-                write!(self.stdout, "{}", menu.title).unwrap();
-                execute!(
-                    self.stdout,
-                    MoveToColumn(0),
-                    MoveDown(2),
-                ).unwrap();
-
-                for index in 0..menu.items.len() {
-                    let format = if index == menu.selected_item {
-                        write!(self.stdout, "* {}", menu.items[index].label).unwrap();
-                    } else {
-                        write!(self.stdout, "  {}", menu.items[index].label).unwrap();
-                    };
-
-                    execute!(
-                        self.stdout,
-                        MoveToColumn(0),
-                        MoveDown(1),
-                    ).unwrap();
+            RenderState::Training(playfield, trainer) => {
+                if let Some(layouts) = self.layout_playfields(&playfield.state, TRAINER_INFO_HEIGHT, 1) {
+                    self.render_playfield(playfield, 0, "Trainer", &layouts[0], settings);
+                    self.render_trainer_info(trainer, &layouts[0], settings);
+                }
+            }
+            RenderState::Versus(player_playfield, bot_playfield) => {
+                if let Some(layouts) = self.layout_playfields(&player_playfield.state, 0, 2) {
+                    self.render_playfield(player_playfield, 0, "You", &layouts[0], settings);
+                    self.render_playfield(bot_playfield, 1, "AI", &layouts[1], settings);
                 }
             }
+            RenderState::InMenu(menu) => {
+                self.render_menu(&menu.title, &menu.lines(settings), menu.selected_item(), settings);
+            }
+            RenderState::Results(menu, stats) => {
+                let lines = menu.lines(settings);
+                self.render_menu(&menu.title, &lines, menu.selected_item(), settings);
+                self.render_results(stats, 3 + lines.len() as u16, settings);
+            }
+            RenderState::Profile(name, profile) => {
+                self.render_profile(name, profile, settings);
+            }
+            RenderState::ChoosingTheme(picker) => {
+                self.render_theme_picker(picker, settings);
+            }
+            RenderState::Prompt(text) => {
+                self.frame.put_str(0, 0, text);
+            }
         }
+
+        self.present();
+    }
+
+    fn needs_redraw(&self) -> bool {
+        let size = terminal_size();
+        let is_animating = self.animations.iter().any(|animations| animations.is_running());
+        return size != (self.frame.width, self.frame.height) || is_animating;
     }
 }
 
 impl TerminalRenderer {
-    fn render_tetris_state(&mut self, state: &TetrisState) {
-        let game_board_start_column: u16 = 15;
-        let next_pieces_board_start_column: u16 = 38;
-        let held_piece_board_start_column: u16 = 0;
+    // Sends only the cells that changed since the last frame, in a single batch
+    fn present(&mut self) {
+        queue!(self.output, BeginSynchronizedUpdate).unwrap();
 
-        execute!(
-            self.stdout,
-            Clear(ClearType::All),
-            MoveToColumn(0),
-            MoveToRow(0),
-        ).unwrap();
+        let previous_frame = match self.previous_frame.take() {
+            Some(previous_frame) if previous_frame.width == self.frame.width && previous_frame.height == self.frame.height => Some(previous_frame),
+            _ => {
+                queue!(self.output, Clear(ClearType::All)).unwrap();
+                None
+            }
+        };
 
-        execute!(
-            self.stdout,
-            MoveToColumn(held_piece_board_start_column),
-            MoveToRow(0),
-        ).unwrap();
+        let mut current_style = Style::default();
+        queue!(self.output, ResetColor).unwrap();
 
-        write!(self.stdout, "Hold").unwrap();
+        for row in 0..self.frame.height {
+            // Where the terminal cursor will be after the last print, so adjacent cells don't need a move
+            let mut cursor_column: Option<u16> = None;
 
-        execute!(
-            self.stdout,
-            MoveToColumn(game_board_start_column),
-            MoveToRow(0),
-        ).unwrap();
+            for column in 0..self.frame.width {
+                let cell = self.frame.cell(column, row);
+                let is_unchanged = previous_frame.as_ref().is_some_and(|previous_frame| previous_frame.cell(column, row) == cell);
 
-        write!(self.stdout, "Game").unwrap();
+                let (symbol, style) = match cell {
+                    Cell::Symbol(symbol, style) if !is_unchanged => (symbol, style),
+                    _ => continue,
+                };
 
-        execute!(
-            self.stdout,
-            MoveToColumn(next_pieces_board_start_column),
-            MoveToRow(0),
-        ).unwrap();
+                if cursor_column != Some(column) {
+                    queue!(self.output, MoveTo(column, row)).unwrap();
+                }
 
-        write!(self.stdout, "Next").unwrap();
+                if style != current_style {
+                    queue!(self.output, ResetColor).unwrap();
+                    if let Some(foreground) = style.foreground {
+                        queue!(self.output, SetForegroundColor(foreground)).unwrap();
+                    }
+                    if let Some(background) = style.background {
+                        queue!(self.output, SetBackgroundColor(background)).unwrap();
+                    }
+                    current_style = style;
+                }
 
-        execute!(
-            self.stdout,
-            MoveToColumn(game_board_start_column),
-            MoveToRow(1),
-        ).unwrap();
+                queue!(self.output, Print(symbol)).unwrap();
 
-        for line in state.board.blocks {
-            for block in line {
-                write!(self.stdout, "{}", char_for_block_type(&block.block_type)).unwrap();
+                let is_wide = column + 1 < self.frame.width && self.frame.cell(column + 1, row) == Cell::Continuation;
+                cursor_column = Some(column + if is_wide { 2 } else { 1 });
+            }
+        }
+
+        queue!(self.output, ResetColor, EndSynchronizedUpdate).unwrap();
+        self.output.flush().unwrap();
+
+        self.previous_frame = Some(self.frame.clone());
+    }
+
+    // Lays out the playfields for the current frame, or says the terminal is too small and returns None
+    fn layout_playfields(&mut self, state: &TetrisState, info_height: u16, playfield_count: u16) -> Option<Vec<PlayfieldLayout>> {
+        let sizes = PanelSizes {
+            board: (2 * state.board.width() as u16, (state.board.height() - state.board.buffer_height()) as u16 + PARTIAL_ROWS),
+            hold: (2 * state.held_piece_board.blocks[0].len() as u16, state.held_piece_board.blocks.len() as u16),
+            next: (2 * state.next_pieces_board.blocks.first().map_or(0, |line| line.len()) as u16, state.next_pieces_board.blocks.len() as u16),
+            hud: (HUD_WIDTH, HUD_STATS_HEIGHT + 1 + HUD_ACTION_TEXT_HEIGHT),
+            info_height,
+        };
+
+        return match layout_playfields((self.frame.width, self.frame.height), &sizes, playfield_count) {
+            Ok(layouts) => Some(layouts),
+            Err(minimum_sizes) => {
+                self.render_terminal_too_small(&minimum_sizes);
+                None
             }
+        }
+    }
+
+    fn render_terminal_too_small(&mut self, minimum_sizes: &[(u16, u16)]) {
+        let sizes = minimum_sizes
+            .iter()
+            .map(|(width, height)| format!("{}x{}", width, height))
+            .collect::<Vec<String>>()
+            .join(" or ");
 
-            crossterm::execute!(
-                self.stdout,
-                crossterm::cursor::MoveDown(1),
-                crossterm::cursor::MoveToColumn(game_board_start_column),
-            ).unwrap();
+        let lines = [
+            String::from("Terminal too small"),
+            format!("Now {}x{}, needs {}", self.frame.width, self.frame.height, sizes),
+        ];
+
+        let start_row = self.frame.height.saturating_sub(lines.len() as u16) / 2;
+        for (index, line) in lines.iter().enumerate() {
+            let column = self.frame.width.saturating_sub(line.len() as u16) / 2;
+            self.frame.put_str(column, start_row + index as u16, line);
         }
+    }
 
-        crossterm::execute!(
-            self.stdout,
-            crossterm::cursor::MoveToRow(1),
-            crossterm::cursor::MoveToColumn(next_pieces_board_start_column),
-        ).unwrap();
+    fn render_playfield(&mut self, playfield: &Playfield, index: usize, title: &str, layout: &PlayfieldLayout, settings: &Settings) {
+        let state = &playfield.state;
+        self.animations[index].observe(playfield, settings.line_clear_delay);
+        let board_width = state.board.width();
+        let visible_rows = state.board.buffer_height()..state.board.height();
+        // The bottom row of the buffer peeks out above the visible field
+        let first_drawn_row = state.board.buffer_height() - PARTIAL_ROWS as usize;
 
-        for line in state.next_pieces_board.blocks {
-            for block in line {
-                write!(self.stdout, "{}", char_for_block_type(&block.block_type)).unwrap();
+        self.render_box(&layout.hold, "Hold", settings);
+        self.render_box(&layout.board, title, settings);
+        if let Some(next_rect) = &layout.next {
+            self.render_box(next_rect, "Next", settings);
+        }
+
+        let partial_block_glyph = if settings.ascii { ASCII_PARTIAL_BLOCK_GLYPH } else { PARTIAL_BLOCK_GLYPH };
+        for (column_index, block) in state.board.blocks[first_drawn_row].iter().enumerate() {
+            if let Some(style) = partial_block_style(&block.block_type, settings) {
+                self.frame.put_styled_str(layout.board.column + 2 * column_index as u16, layout.board.row, partial_block_glyph, style);
             }
+        }
 
-            crossterm::execute!(
-                self.stdout,
-                crossterm::cursor::MoveDown(1),
-                crossterm::cursor::MoveToColumn(next_pieces_board_start_column),
-            ).unwrap();
+        for row_index in visible_rows.clone() {
+            let mut column = layout.board.column;
+            for (column_index, block) in state.board.blocks[row_index].iter().enumerate() {
+                let block_type = hint_overlay_block_type(state, &block.block_type, row_index, column_index);
+                let row = layout.board.row + (row_index - first_drawn_row) as u16;
+                column += match self.animations[index].effect(row_index, column_index, board_width, visible_rows.clone()) {
+                    Some(CellEffect::Flash) => {
+                        let (text, style) = flash_glyph(settings);
+                        self.frame.put_styled_str(column, row, text, style)
+                    }
+                    Some(CellEffect::Erase) => self.put_block(column, row, &BlockType::Empty, settings),
+                    None                    => self.put_block(column, row, &block_type, settings),
+                };
+            }
         }
 
-        crossterm::execute!(
-            self.stdout,
-            crossterm::cursor::MoveToRow(1),
-            crossterm::cursor::MoveToColumn(held_piece_board_start_column),
-        ).unwrap();
+        // A row of the wall per pending line, from the floor up
+        let meter_rows = (state.pending_garbage as usize * state.board.mino_size()).min(visible_rows.len()) as u16;
+        let meter_glyph = if settings.ascii { ASCII_GARBAGE_METER_GLYPH } else { GARBAGE_METER_GLYPH };
+        let meter_style = Style { foreground: fit_color(GARBAGE_METER_COLOR, &settings.block_style), background: None };
+        let floor_row = layout.board.row + (state.board.height() - first_drawn_row) as u16;
+        for row in floor_row - meter_rows..floor_row {
+            self.frame.put_styled_str(layout.board.column - 1, row, meter_glyph, meter_style);
+        }
+
+        if let Some(next_rect) = &layout.next {
+            self.render_preview(&state.next_pieces_board.blocks, &state.next_pieces_board.half_block_offsets, next_rect, settings);
+        }
+        self.render_preview(&state.held_piece_board.blocks, &state.held_piece_board.half_block_offsets, &layout.hold, settings);
+
+        if let Some(hud_rect) = &layout.hud {
+            self.render_box(hud_rect, "Stats", settings);
+            self.render_hud(playfield.hud, state, hud_rect, settings);
+        }
+    }
 
-        for line in state.held_piece_board.blocks {
+    // Empty cells are left blank, since rows that are offset by half a block can't line up with a grid
+    fn render_preview(&mut self, blocks: &[Vec<Block>], half_block_offsets: &[bool], rect: &Rect, settings: &Settings) {
+        for (row_index, line) in blocks.iter().enumerate() {
+            let mut column = rect.column + if half_block_offsets[row_index] { 1 } else { 0 };
             for block in line {
-                write!(self.stdout, "{}", char_for_block_type(&block.block_type)).unwrap();
+                if block.block_type == BlockType::Empty {
+                    column += 2;
+                } else {
+                    column += self.put_block(column, rect.row + row_index as u16, &block.block_type, settings);
+                }
+            }
+        }
+    }
+
+    // A box-drawing frame just outside `rect`, with the title set into the top edge
+    fn render_box(&mut self, rect: &Rect, title: &str, settings: &Settings) {
+        let border_style = text_style(&settings.theme.border, settings);
+        let hud_style = text_style(&settings.theme.hud, settings);
+        let left = rect.column - 1;
+        let right = rect.column + rect.width;
+
+        let (top_left, top_right, bottom_left, bottom_right, horizontal, vertical) = if settings.ascii {
+            ("+", "+", "+", "+", "-", "|")
+        } else {
+            ("┌", "┐", "└", "┘", "─", "│")
+        };
+
+        let top = format!("{}{}{}", top_left, horizontal.repeat(rect.width as usize), top_right);
+        let bottom = format!("{}{}{}", bottom_left, horizontal.repeat(rect.width as usize), bottom_right);
+        self.frame.put_styled_str(left, rect.row - 1, &top, border_style);
+        self.frame.put_styled_str(left, rect.row + rect.height, &bottom, border_style);
+
+        for row in rect.row..rect.row + rect.height {
+            self.frame.put_styled_str(left, row, vertical, border_style);
+            self.frame.put_styled_str(right, row, vertical, border_style);
+        }
+
+        if !title.is_empty() && (title.len() as u16) + 2 <= rect.width {
+            self.frame.put_styled_str(rect.column + 1, rect.row - 1, &format!(" {} ", title), hud_style);
+        }
+    }
+
+    // The combo and back-to-back chain come from the engine, the rest from the HUD
+    fn render_hud(&mut self, hud: &Hud, state: &TetrisState, rect: &Rect, settings: &Settings) {
+        let hud_style = text_style(&settings.theme.hud, settings);
+
+        let seconds = hud.time / 1000000;
+        let tenths = hud.time / 100000 % 10;
+        let stats = [
+            ("Score", hud.score.to_string()),
+            ("Level", hud.level().to_string()),
+            ("Lines", hud.lines.to_string()),
+            ("Time",  format!("{}:{:02}.{}", seconds / 60, seconds % 60, tenths)),
+            ("PPS",   format!("{:.2}", hud.pieces_per_second())),
+            ("Combo", format_streak(state.combo)),
+            ("B2B",   format_streak(state.back_to_back)),
+        ];
+
+        for (index, (name, value)) in stats.iter().enumerate() {
+            let value_width = rect.width as usize - name.len();
+            let line = format!("{}{:>value_width$}", name, value);
+            self.frame.put_styled_str(rect.column, rect.row + index as u16, &line, hud_style);
+        }
+
+        if let Some((action_text, fade)) = hud.action_text() {
+            let color = match fade {
+                ActionTextFade::Bright => &settings.theme.menu_highlight,
+                ActionTextFade::Faded  => &settings.theme.border,
+            };
+            let style = text_style(color, settings);
+            let start_row = rect.row + HUD_STATS_HEIGHT + 1;
+
+            for (index, text) in action_text.iter().take(HUD_ACTION_TEXT_HEIGHT as usize).enumerate() {
+                let column = rect.column + rect.width.saturating_sub(text.len() as u16) / 2;
+                self.frame.put_styled_str(column, start_row + index as u16, text, style);
+            }
+        }
+    }
+
+    // Every block takes up two columns, whatever the style
+    fn put_block(&mut self, column: u16, row: u16, block_type: &BlockType, settings: &Settings) -> u16 {
+        let (text, style) = glyph_for_block_type(block_type, settings);
+        return self.frame.put_styled_str(column, row, text, style);
+    }
+
+    fn render_menu(&mut self, title: &str, lines: &[MenuLine], selected_item: usize, settings: &Settings) {
+        let highlight_style = text_style(&settings.theme.menu_highlight, settings);
+        // Disabled items are toned down to the colour of the walls
+        let disabled_style = text_style(&settings.theme.border, settings);
+
+        self.frame.put_str(0, 0, title);
+
+        for (index, line) in lines.iter().enumerate() {
+            let row = 2 + index as u16;
+            if index == selected_item {
+                self.frame.put_styled_str(0, row, &format!("* {}", line.label), highlight_style);
+            } else if !line.is_enabled {
+                self.frame.put_styled_str(0, row, &format!("  {}", line.label), disabled_style);
+            } else {
+                self.frame.put_str(0, row, &format!("  {}", line.label));
+            }
+        }
+    }
+
+    fn render_results(&mut self, stats: &GameStats, start_row: u16, settings: &Settings) {
+        let game = Vec::from([
+            (String::from("Score"),          stats.score.to_string()),
+            (String::from("Lines"),          stats.lines.to_string()),
+            (String::from("Time"),           format_time((stats.time / 1000) as u64)),
+            (String::from("Pieces"),         stats.pieces.to_string()),
+            (String::from("PPS"),            format!("{:.2}", stats.pieces_per_second())),
+            (String::from("KPP"),            format!("{:.2}", stats.keys_per_piece())),
+            (String::from("APM"),            format!("{:.1}", stats.attack_per_minute())),
+            (String::from("Attack"),         stats.attack.to_string()),
+            (String::from("Holds"),          stats.holds.to_string()),
+            (String::from("Finesse faults"), stats.finesse_faults.to_string()),
+            (String::from("Max combo"),      stats.max_combo.to_string()),
+            (String::from("Max B2B"),        stats.max_back_to_back.to_string()),
+            (String::from("Perfect clears"), stats.perfect_clears.to_string()),
+        ]);
+        let clears = CLEAR_KINDS.iter().zip(stats.clears).map(|((_, _, name), count)| (name.to_string(), count.to_string())).collect();
+        let pieces = PIECE_NAMES.iter().zip(stats.pieces_by_type).map(|(name, count)| (name.to_string(), count.to_string())).collect();
+
+        let heading_style = text_style(&settings.theme.menu_highlight, settings);
+        let hud_style = text_style(&settings.theme.hud, settings);
+        let columns: [(&str, Vec<(String, String)>); 3] = [("Game", game), ("Clears", clears), ("Pieces", pieces)];
+
+        // Each column is a heading and then a label and a number per row, as narrow as they allow
+        let mut column = 0;
+        for (heading, rows) in columns.iter() {
+            let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + 1;
+            let value_width = rows.iter().map(|(_, value)| value.len()).max().unwrap_or(0);
+
+            self.frame.put_styled_str(column as u16, start_row, heading, heading_style);
+            for (row, (label, value)) in rows.iter().enumerate() {
+                let text = format!("{:<label_width$}{:>value_width$}", label, value);
+                self.frame.put_styled_str(column as u16, start_row + 1 + row as u16, &text, hud_style);
+            }
+
+            column += label_width + value_width + RESULTS_COLUMN_GAP;
+        }
+    }
+
+    fn render_profile(&mut self, name: &str, profile: &Profile, settings: &Settings) {
+        let heading_style = text_style(&settings.theme.menu_highlight, settings);
+        let hud_style = text_style(&settings.theme.hud, settings);
+
+        self.frame.put_str(0, 0, &format!("Profile: {}", name));
+        let minutes_played = profile.time_played / 60000;
+        self.frame.put_styled_str(0, 2, &format!(
+            "Games played: {}   Time played: {}h {:02}m   Lines cleared: {}",
+            profile.games_played,
+            minutes_played / 60,
+            minutes_played % 60,
+            profile.lines_cleared,
+        ), hud_style);
+
+        let trainer = &profile.finesse_trainer;
+        self.frame.put_styled_str(0, 3, &format!(
+            "Finesse trainer: {} pieces, {} clean   Accuracy: {}   Last {}: {}",
+            trainer.pieces,
+            trainer.clean_pieces,
+            format_accuracy(trainer.overall_accuracy()),
+            trainer.recent_pieces.len(),
+            format_accuracy(trainer.recent_accuracy()),
+        ), hud_style);
+
+        self.frame.put_styled_str(0, 5, "Personal bests", heading_style);
+        let mut row = 6;
+        for (mode, personal_bests) in profile.personal_bests() {
+            if let Some(best) = personal_bests.last() {
+                let line = format!("{:<10}{:<14}set {}, personal best #{}", mode, format_result(mode, best), best.date, personal_bests.len());
+                self.frame.put_styled_str(0, row, &line, hud_style);
+                row += 1;
             }
+        }
+        if row == 6 {
+            self.frame.put_styled_str(0, row, "None yet. Finish a marathon or a sprint to set one.", hud_style);
+            row += 1;
+        }
 
-            crossterm::execute!(
-                self.stdout,
-                crossterm::cursor::MoveDown(1),
-                crossterm::cursor::MoveToColumn(held_piece_board_start_column),
-            ).unwrap();
+        let charts = [("sprint", "Recent sprint times"), ("marathon", "Recent marathon scores")];
+        for (index, (mode, title)) in charts.iter().enumerate() {
+            let column = index as u16 * CHART_COLUMN_WIDTH;
+            let games = profile.recent_games(mode);
+            self.frame.put_styled_str(column, row + 1, title, heading_style);
+
+            if games.is_empty() {
+                self.frame.put_styled_str(column, row + 2, "Nothing to chart yet", hud_style);
+                continue;
+            }
+
+            let values: Vec<f64> = games.iter().map(|game| chart_value(mode, game) as f64).collect();
+            for (chart_row, line) in bar_chart(&values, CHART_HEIGHT, settings.ascii).iter().enumerate() {
+                self.frame.put_styled_str(column, row + 2 + chart_row as u16, line, hud_style);
+            }
+
+            // The top of the chart is the highest game and the bottom the lowest
+            let label_column = column + values.len() as u16 + 1;
+            if let Some(highest) = games.iter().max_by_key(|game| chart_value(mode, game)) {
+                self.frame.put_styled_str(label_column, row + 2, &format_result(mode, highest), hud_style);
+            }
+            if let Some(lowest) = games.iter().min_by_key(|game| chart_value(mode, game)) {
+                self.frame.put_styled_str(label_column, row + 1 + CHART_HEIGHT, &format_result(mode, lowest), hud_style);
+            }
         }
+
+        self.frame.put_str(0, row + 3 + CHART_HEIGHT, "Oldest games on the left. Escape to go back");
+    }
+
+    fn render_theme_picker(&mut self, picker: &ThemePicker, settings: &Settings) {
+        let lines: Vec<MenuLine> = picker.themes.iter().map(|theme| MenuLine { label: theme.name.clone(), is_enabled: true }).collect();
+        self.render_menu("Theme", &lines, picker.selected_theme, settings);
+
+        let preview_rows: [(BlockType, &str); 14] = [
+            (BlockType::O,               "O"),
+            (BlockType::I,               "I"),
+            (BlockType::L,               "L"),
+            (BlockType::J,               "J"),
+            (BlockType::S,               "S"),
+            (BlockType::Z,               "Z"),
+            (BlockType::T,               "T"),
+            (BlockType::Garbage,         "Garbage"),
+            (BlockType::Ghost,           "Ghost"),
+            (BlockType::Outline,         "Outline"),
+            (BlockType::Target,          "Target"),
+            (BlockType::Hint,            "Hint"),
+            (BlockType::AlternativeHint, "Second hint"),
+            (BlockType::Empty,           "Empty"),
+        ];
+
+        let hud_style = text_style(&settings.theme.hud, settings);
+        let blocks_start_column = THEME_PREVIEW_START_COLUMN + 1;
+        self.frame.put_styled_str(blocks_start_column, 0, "Preview", hud_style);
+
+        for (index, (block_type, name)) in preview_rows.iter().enumerate() {
+            let row = 2 + index as u16;
+            let mut column = blocks_start_column;
+            for _ in 0..4 {
+                column += self.put_block(column, row, block_type, settings);
+            }
+            self.frame.put_styled_str(column + 2, row, name, hud_style);
+        }
+
+        let preview_rect = Rect { column: blocks_start_column, row: 2, width: 8, height: preview_rows.len() as u16 };
+        self.render_box(&preview_rect, "", settings);
+
+        let help_row = 4 + preview_rows.len() as u16;
+        self.frame.put_str(0, help_row, "Enter to keep the theme, Escape to go back without changing it");
+        if settings.block_style == BlockStyle::Emoji || settings.block_style == BlockStyle::Monochrome {
+            self.frame.put_str(0, help_row + 1, "Block colours only show with the 16, 256 and true colour block styles");
+        }
+    }
+
+    fn render_trainer_info(&mut self, trainer: &FinesseTrainer, layout: &PlayfieldLayout, settings: &Settings) {
+        let info_start_row = layout.info.row;
+        let hud_style = text_style(&settings.theme.hud, settings);
+
+        self.frame.put_styled_str(layout.info.column, info_start_row, &format!(
+            "Pieces: {}  Clean: {}  Accuracy: {}  Last {}: {}",
+            trainer.pieces(),
+            trainer.clean_pieces(),
+            format_accuracy(trainer.overall_accuracy()),
+            RECENT_PIECES_WINDOW,
+            format_accuracy(trainer.recent_accuracy()),
+        ), hud_style);
+
+        let verdict_text = match &trainer.last_verdict {
+            None => String::from("Place the piece on the target with as few keys as possible"),
+            Some(verdict) if verdict.is_clean() => String::from("Clean!"),
+            Some(verdict) if !verdict.hit_target => format!("Missed the target. Fastest way there: {}", format_inputs(&verdict.target_inputs, settings.ascii)),
+            Some(verdict) => match &verdict.finesse {
+                Some(finesse) => format!(
+                    "Finesse fault: {} keys, {} needed: {}",
+                    finesse.key_presses,
+                    finesse_cost(&finesse.expected_inputs),
+                    format_inputs(&finesse.expected_inputs, settings.ascii),
+                ),
+                None => String::new(),
+            },
+        };
+
+        self.frame.put_styled_str(layout.info.column, info_start_row + 1, &verdict_text, hud_style);
     }
 
-    pub fn new() -> TerminalRenderer {
+    pub fn new(output: Box<dyn Write>) -> TerminalRenderer {
         return TerminalRenderer {
-            stdout: stdout(),
+            output,
+            frame: FrameBuffer::new(0, 0),
+            previous_frame: None,
+            animations: [PlayfieldAnimations::new(), PlayfieldAnimations::new()],
         }
     }
 
@@ -172,28 +638,331 @@ impl TerminalRenderer {
     }
 }
 
-fn char_for_block_type(block_type: &BlockType) -> char {
+pub fn terminal_size() -> (u16, u16) {
+    return crossterm::terminal::size().unwrap_or(FALLBACK_TERMINAL_SIZE);
+}
+
+// Hints are drawn over empty cells and the ghost, but never over real blocks
+fn hint_overlay_block_type(state: &TetrisState, block_type: &BlockType, row_index: usize, column_index: usize) -> BlockType {
+    if *block_type != BlockType::Empty && *block_type != BlockType::Ghost {
+        return *block_type;
+    }
+
+    for (hint_index, hint) in state.hints.iter().enumerate() {
+        let covers_cell = hint.cells().contains(&state.board.mino_at(row_index, column_index));
+        if covers_cell {
+            return if hint_index == 0 { BlockType::Hint } else { BlockType::AlternativeHint };
+        }
+    }
+
+    return *block_type;
+}
+
+fn glyph_for_block_type<'a>(block_type: &BlockType, settings: &'a Settings) -> (&'a str, Style) {
+    return match settings.block_style {
+        BlockStyle::Emoji      => (emoji_for_block_type(block_type), Style::default()),
+        BlockStyle::Monochrome => (monochrome_text_for_block_type(block_type), Style::default()),
+        BlockStyle::Ansi16 | BlockStyle::Ansi256 | BlockStyle::TrueColor => {
+            let appearance = settings.theme.blocks.get(block_type);
+            let style = Style {
+                foreground: appearance.fg.and_then(|color| fit_color(color.0, &settings.block_style)),
+                background: appearance.bg.and_then(|color| fit_color(color.0, &settings.block_style)),
+            };
+            (appearance.glyph.as_str(), style)
+        }
+    }
+}
+
+// Only the bottom half of a block shows in the partly visible row above the field
+fn partial_block_style(block_type: &BlockType, settings: &Settings) -> Option<Style> {
+    if *block_type == BlockType::Empty {
+        return None;
+    }
+
+    if settings.block_style == BlockStyle::Monochrome {
+        return Some(Style::default());
+    }
+
+    let appearance = settings.theme.blocks.get(block_type);
+    let color = appearance.bg.or(appearance.fg).and_then(|color| fit_color(color.0, &settings.block_style));
+    return Some(Style { foreground: color, background: None });
+}
+
+// A solid block for animations, in the HUD colour where there is colour
+fn flash_glyph(settings: &Settings) -> (&'static str, Style) {
+    return match settings.block_style {
+        BlockStyle::Emoji      => ("⬜", Style::default()),
+        BlockStyle::Monochrome => ("##", Style::default()),
+        BlockStyle::Ansi16 | BlockStyle::Ansi256 | BlockStyle::TrueColor => {
+            ("  ", Style { foreground: None, background: fit_color(settings.theme.hud.0, &settings.block_style) })
+        }
+    }
+}
+
+fn text_style(color: &ThemeColor, settings: &Settings) -> Style {
+    return Style { foreground: fit_color(color.0, &settings.block_style), background: None };
+}
+
+// Brings a theme colour down to what the block style says the terminal can show.
+// Terminals that can show emoji can be trusted with 256 colours for the text around them.
+fn fit_color(color: Color, block_style: &BlockStyle) -> Option<Color> {
+    return match (block_style, color) {
+        (BlockStyle::Monochrome, _)                             => None,
+        (BlockStyle::TrueColor, color)                          => Some(color),
+        (BlockStyle::Ansi256 | BlockStyle::Emoji, Color::Rgb { r, g, b }) => Some(Color::AnsiValue(nearest_ansi256(r, g, b))),
+        (BlockStyle::Ansi16, Color::Rgb { r, g, b })            => Some(nearest_ansi16(r, g, b)),
+        (BlockStyle::Ansi16, Color::AnsiValue(index))           => {
+            let (r, g, b) = ansi256_to_rgb(index);
+            Some(nearest_ansi16(r, g, b))
+        }
+        (_, color)                                              => Some(color),
+    }
+}
+
+const ANSI16_PALETTE: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black,       (0, 0, 0)),
+    (Color::DarkRed,     (128, 0, 0)),
+    (Color::DarkGreen,   (0, 128, 0)),
+    (Color::DarkYellow,  (128, 128, 0)),
+    (Color::DarkBlue,    (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan,    (0, 128, 128)),
+    (Color::Grey,        (192, 192, 192)),
+    (Color::DarkGrey,    (128, 128, 128)),
+    (Color::Red,         (255, 0, 0)),
+    (Color::Green,       (0, 255, 0)),
+    (Color::Yellow,      (255, 255, 0)),
+    (Color::Blue,        (0, 0, 255)),
+    (Color::Magenta,     (255, 0, 255)),
+    (Color::Cyan,        (0, 255, 255)),
+    (Color::White,       (255, 255, 255)),
+];
+
+// The levels of each channel in the 6x6x6 colour cube that starts at index 16
+const ANSI256_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn color_distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let dr = r1 as i32 - r2 as i32;
+    let dg = g1 as i32 - g2 as i32;
+    let db = b1 as i32 - b2 as i32;
+    return (dr * dr + dg * dg + db * db) as u32;
+}
+
+fn nearest_ansi16(r: u8, g: u8, b: u8) -> Color {
+    return ANSI16_PALETTE
+        .iter()
+        .min_by_key(|(_, rgb)| color_distance(*rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap();
+}
+
+fn nearest_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let nearest_level = |channel: u8| {
+        return (0..ANSI256_CUBE_LEVELS.len())
+            .min_by_key(|index| ANSI256_CUBE_LEVELS[*index].abs_diff(channel))
+            .unwrap() as u8;
+    };
+
+    let cube_index = 16 + 36 * nearest_level(r) + 6 * nearest_level(g) + nearest_level(b);
+
+    // The 24 step grey ramp from 232 onwards is finer than the cube's greys
+    let average = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+    let grey_step = (average.saturating_sub(8) / 10).min(23);
+    let grey_index = 232 + grey_step;
+
+    if color_distance(ansi256_to_rgb(grey_index), (r, g, b)) < color_distance(ansi256_to_rgb(cube_index), (r, g, b)) {
+        return grey_index;
+    }
+
+    return cube_index;
+}
+
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    return match index {
+        0..=15 => ANSI16_PALETTE[index as usize].1,
+        16..=231 => {
+            let cube_index = index - 16;
+            (
+                ANSI256_CUBE_LEVELS[(cube_index / 36) as usize],
+                ANSI256_CUBE_LEVELS[(cube_index / 6 % 6) as usize],
+                ANSI256_CUBE_LEVELS[(cube_index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn emoji_for_block_type(block_type: &BlockType) -> &'static str {
+   match block_type {
+       BlockType::O =>               "🟨",
+       BlockType::I =>               "🟪",
+       BlockType::L =>               "🟧",
+       BlockType::J =>               "🟦",
+       BlockType::S =>               "🟩",
+       BlockType::Z =>               "🟥",
+       BlockType::T =>               "🟫",
+       BlockType::Garbage =>         "⬜",
+       BlockType::Ghost =>           "🤍",
+       BlockType::Outline =>         "🔲",
+       BlockType::Target =>          "🔳",
+       BlockType::Hint =>            "🟢",
+       BlockType::AlternativeHint => "⚪",
+       BlockType::Empty =>           "🖤",
+   }
+}
+
+fn monochrome_text_for_block_type(block_type: &BlockType) -> &'static str {
    match block_type {
-       BlockType::O =>     '🟨',
-       BlockType::I =>     '🟪',
-       BlockType::L =>     '🟧',
-       BlockType::J =>     '🟦',
-       BlockType::S =>     '🟩',
-       BlockType::Z =>     '🟥',
-       BlockType::T =>     '🟫',
-       BlockType::Ghost => '🤍',
-       BlockType::Empty => '🖤',
+       BlockType::O =>               "OO",
+       BlockType::I =>               "II",
+       BlockType::L =>               "LL",
+       BlockType::J =>               "JJ",
+       BlockType::S =>               "SS",
+       BlockType::Z =>               "ZZ",
+       BlockType::T =>               "TT",
+       BlockType::Garbage =>         "##",
+       BlockType::Ghost =>           "::",
+       BlockType::Outline =>         "()",
+       BlockType::Target =>          "<>",
+       BlockType::Hint =>            "++",
+       BlockType::AlternativeHint => "--",
+       BlockType::Empty =>           " .",
    }
 }
 
+// Asks the terminal to hold off drawing until the whole frame has arrived (DEC private mode 2026).
+// Terminals that don't support it ignore the sequence.
+struct BeginSynchronizedUpdate;
+
+impl Command for BeginSynchronizedUpdate {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        return f.write_str("\x1b[?2026h");
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        return Ok(());
+    }
+}
+
+struct EndSynchronizedUpdate;
+
+impl Command for EndSynchronizedUpdate {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        return f.write_str("\x1b[?2026l");
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        return Ok(());
+    }
+}
+
+// Combos and back-to-backs only count once there's been more than one clear in a row
+fn format_streak(streak: Option<u32>) -> String {
+    return match streak {
+        Some(streak) if streak > 0 => format!("x{}", streak),
+        _ => String::from("-"),
+    }
+}
+
+fn format_accuracy(accuracy: Option<f64>) -> String {
+    return match accuracy {
+        Some(accuracy) => format!("{:.0}%", accuracy * 100.0),
+        None => String::from("-"),
+    }
+}
+
+fn format_inputs(inputs: &[Input], ascii: bool) -> String {
+    return inputs
+        .iter()
+        .filter(|input| **input != Input::HardDrop)
+        .map(|input| if ascii { ascii_input_name(input) } else { input_symbol(input) })
+        .collect::<Vec<&str>>()
+        .join(" ");
+}
+
+fn input_symbol(input: &Input) -> &'static str {
+    return match input {
+        Input::Left                   => "←",
+        Input::Right                  => "→",
+        Input::DasLeft                => "⇇",
+        Input::DasRight               => "⇉",
+        Input::SoftDrop               => "↓",
+        Input::SonicDrop              => "⇊",
+        Input::RotateClockwise        => "↻",
+        Input::RotateCounterclockwise => "↺",
+        Input::Hold                   => "Hold",
+        Input::HardDrop               => "Drop",
+    }
+}
+
+fn ascii_input_name(input: &Input) -> &'static str {
+    return match input {
+        Input::Left                   => "<",
+        Input::Right                  => ">",
+        Input::DasLeft                => "<<",
+        Input::DasRight               => ">>",
+        Input::SoftDrop               => "v",
+        Input::SonicDrop              => "vv",
+        Input::RotateClockwise        => "cw",
+        Input::RotateCounterclockwise => "ccw",
+        Input::Hold                   => "Hold",
+        Input::HardDrop               => "Drop",
+    }
+}
+
 fn read_until_empty(std_in: &mut Stdin) {
     let mut junk_input = Vec::new();
-    let mut bytes: usize = 0;
     loop {
-        bytes = std_in.read(&mut junk_input).unwrap();
-        write!(stderr(), "{}\n", bytes).unwrap();
+        let bytes = std_in.read(&mut junk_input).unwrap();
+        eprintln!("{}", bytes);
         if bytes == 0 {
             break
         }
     }
 }
+
+// What a game is measured by: time for sprints, score for everything else
+fn chart_value(mode: &str, game: &ProfileGame) -> u64 {
+    return if mode == "sprint" { game.time } else { game.score as u64 };
+}
+
+fn format_result(mode: &str, game: &ProfileGame) -> String {
+    return if mode == "sprint" { format_time(game.time) } else { format!("{} pts", game.score) };
+}
+
+// A column per value, scaled so the lowest is a sliver and the highest fills the chart. Rows
+// are returned top first.
+fn bar_chart(values: &[f64], height: u16, ascii: bool) -> Vec<String> {
+    let lowest = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let highest = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let levels = height as f64 * 8.0;
+
+    let eighths: Vec<usize> = values
+        .iter()
+        .map(|value| match highest > lowest {
+            true  => 1 + ((value - lowest) / (highest - lowest) * (levels - 1.0)).round() as usize,
+            false => levels as usize,
+        })
+        .collect();
+
+    return (0..height as usize)
+        .rev()
+        .map(|level| {
+            eighths
+                .iter()
+                .map(|eighths| {
+                    let filled = eighths.saturating_sub(level * 8).min(8);
+                    match ascii {
+                        true  => if filled >= 4 { "#" } else { " " },
+                        false => BAR_GLYPHS[filled],
+                    }
+                })
+                .collect()
+        })
+        .collect();
+}
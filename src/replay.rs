@@ -0,0 +1,151 @@
+// A game is fully determined by its seed, its rules and the keys pressed on each frame, so that's
+// all a replay keeps. Frames are never merged, since gravity only ticks once per frame. Time spent
+// paused isn't kept: a frame ends at the pause, and the next one starts at the resume.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::game::{mode_from_name, mode_name, GameMode, Key};
+use crate::settings::{name_of, named, stack_visibility_name, Settings, GAME_KEYS, RANDOMIZERS, ROTATION_SYSTEMS, STACK_VISIBILITIES};
+
+// Marks a key that was auto-repeated rather than pressed
+const REPEAT_MARKER: &str = "+";
+
+pub struct Frame {
+    pub delta_time: u128,
+    pub keys: Vec<Key>,
+    pub repeated_keys: Vec<Key>,
+}
+
+#[derive(Serialize)]
+#[derive(Deserialize)]
+pub struct Replay {
+    pub mode: String,
+    pub seed: u64,
+    randomizer: String,
+    rotation_system: String,
+    // In milliseconds
+    lock_delay: u64,
+    hold: bool,
+    board_width: usize,
+    board_height: usize,
+    preview: usize,
+    animations: bool,
+    stack: String,
+    fade_seconds: u32,
+    flash_stack: bool,
+    // One per frame: the microseconds it took, then the names of the keys from the controls
+    frames: Vec<String>,
+}
+
+impl Replay {
+    pub fn new(mode: GameMode, big: bool, seed: u64, settings: &Settings) -> Replay {
+        return Replay {
+            mode: mode_name(mode, big).to_string(),
+            seed,
+            randomizer: name_of(&RANDOMIZERS, &settings.randomizer).to_string(),
+            rotation_system: name_of(&ROTATION_SYSTEMS, &settings.rotation_system).to_string(),
+            lock_delay: settings.lock_delay as u64,
+            hold: settings.hold,
+            board_width: settings.board_width,
+            board_height: settings.board_height,
+            preview: settings.preview_length,
+            animations: settings.animations,
+            stack: stack_visibility_name(&settings.stack_visibility).to_string(),
+            fade_seconds: settings.fade_seconds,
+            flash_stack: settings.flash_stack,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        return toml::from_str(&text).map_err(|error| format!("{}: {}", path.display(), error.to_string().trim()));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string_pretty(self).map_err(|error| error.to_string())?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+        }
+
+        return fs::write(path, text).map_err(|error| format!("{}: {}", path.display(), error));
+    }
+
+    pub fn has_frames(&self) -> bool {
+        return !self.frames.is_empty();
+    }
+
+    pub fn start_frame(&mut self, delta_time: u128) {
+        self.frames.push(delta_time.to_string());
+    }
+
+    // Adds a key the game acted on in the current frame. Escape pauses just as Pause does, and
+    // Pause also stands for resuming, so playback skips the paused time like the game did.
+    pub fn record_key(&mut self, key: &Key, is_repeat: bool) {
+        let key = if *key == Key::Escape { &Key::Pause } else { key };
+        let name = match GAME_KEYS.iter().find(|(game_key, _, _)| game_key == key) {
+            Some((_, name, _)) => name,
+            None               => return,
+        };
+
+        if let Some(frame) = self.frames.last_mut() {
+            frame.push(' ');
+            if is_repeat {
+                frame.push_str(REPEAT_MARKER);
+            }
+            frame.push_str(name);
+        }
+    }
+
+    pub fn game_mode(&self) -> Result<(GameMode, bool), String> {
+        return mode_from_name(&self.mode).ok_or(format!("unknown mode '{}'", self.mode));
+    }
+
+    // Puts back the rules the game was played with, and the visuals that change what can be seen
+    pub fn apply_to(&self, settings: &mut Settings) -> Result<(), String> {
+        settings.randomizer = named(&RANDOMIZERS, &self.randomizer).ok_or(format!("unknown randomizer '{}'", self.randomizer))?;
+        settings.rotation_system = named(&ROTATION_SYSTEMS, &self.rotation_system).ok_or(format!("unknown rotation system '{}'", self.rotation_system))?;
+        settings.fade_seconds = self.fade_seconds;
+        settings.set_stack_visibility(named(&STACK_VISIBILITIES, &self.stack).ok_or(format!("unknown stack '{}'", self.stack))?);
+        settings.lock_delay = self.lock_delay as u128;
+        settings.hold = self.hold;
+        settings.board_width = self.board_width;
+        settings.board_height = self.board_height;
+        settings.preview_length = self.preview;
+        settings.animations = self.animations;
+        settings.flash_stack = self.flash_stack;
+        return Ok(());
+    }
+
+    pub fn frames(&self) -> Result<Vec<Frame>, String> {
+        return self.frames.iter().enumerate().map(|(index, frame)| parse_frame(frame).ok_or(format!("frame {}: can't read '{}'", index + 1, frame))).collect();
+    }
+}
+
+fn parse_frame(text: &str) -> Option<Frame> {
+    let mut words = text.split_whitespace();
+    let mut frame = Frame { delta_time: words.next()?.parse().ok()?, keys: Vec::new(), repeated_keys: Vec::new() };
+
+    for word in words {
+        let (name, is_repeat) = match word.strip_prefix(REPEAT_MARKER) {
+            Some(name) => (name, true),
+            None       => (word, false),
+        };
+
+        let (key, _, _) = GAME_KEYS.iter().find(|(_, game_key_name, _)| *game_key_name == name)?;
+        frame.keys.push(key.clone());
+        if is_repeat {
+            frame.repeated_keys.push(key.clone());
+        }
+    }
+
+    return Some(frame);
+}
+
+// Named after when the game ended, so they sort in order
+pub fn new_replay_path(mode: &str) -> Option<PathBuf> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    return dirs::data_dir().map(|directory| directory.join("terminal-tetris").join("replays").join(format!("{}-{}.toml", seconds, mode)));
+}
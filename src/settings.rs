@@ -0,0 +1,622 @@
+use std::fs;
+use std::mem::discriminant;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use device_query::Keycode;
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
+use tetris_core::randomizer::Randomizer;
+use tetris_core::tetris::{RotationSystem, StackVisibility, DEFAULT_BOARD_WIDTH, DEFAULT_PREVIEW_LENGTH, DEFAULT_VISIBLE_BOARD_HEIGHT, MAX_BOARD_WIDTH, MAX_PREVIEW_LENGTH, MAX_VISIBLE_BOARD_HEIGHT, MIN_BOARD_WIDTH, MIN_VISIBLE_BOARD_HEIGHT};
+use crate::game::Key;
+use crate::profile::{is_valid_profile_name, DEFAULT_PROFILE};
+use crate::renderer::BlockStyle;
+use crate::theme::Theme;
+
+// Microseconds that cleared lines stay on the board while they're animated
+const LINE_CLEAR_DELAY: u128 = 250000;
+
+// Handling and lock delay are in milliseconds in the settings file and the menus
+const DEFAULT_DAS: u128 = 170;
+const DEFAULT_ARR: u128 = 50;
+const DEFAULT_SOFT_DROP_FACTOR: u32 = 20;
+const DEFAULT_LOCK_DELAY: u128 = 500;
+// Seconds before locked blocks vanish in the fading stack mode
+const DEFAULT_FADE_SECONDS: u32 = 5;
+pub const FADE_SECONDS_RANGE: RangeInclusive<i64> = 1..=30;
+pub const DAS_RANGE: RangeInclusive<i64> = 0..=1000;
+pub const ARR_RANGE: RangeInclusive<i64> = 0..=500;
+pub const SOFT_DROP_FACTOR_RANGE: RangeInclusive<i64> = 1..=40;
+pub const LOCK_DELAY_RANGE: RangeInclusive<i64> = 0..=2000;
+
+pub const BLOCK_STYLES: [(BlockStyle, &str); 5] = [
+    (BlockStyle::Emoji,      "Emoji"),
+    (BlockStyle::Ansi16,     "16 Colors"),
+    (BlockStyle::Ansi256,    "256 Colors"),
+    (BlockStyle::TrueColor,  "True Color"),
+    (BlockStyle::Monochrome, "Monochrome"),
+];
+
+// Fading goes by its name whatever its time, which comes from the fade_seconds setting
+pub const STACK_VISIBILITIES: [(StackVisibility, &str); 4] = [
+    (StackVisibility::Visible,    "Visible"),
+    (StackVisibility::Invisible,  "Invisible"),
+    (StackVisibility::Fading(0),  "Fading"),
+    (StackVisibility::Outline,    "Outline"),
+];
+
+pub const ROTATION_SYSTEMS: [(RotationSystem, &str); 2] = [
+    (RotationSystem::Kicks,   "Kicks"),
+    (RotationSystem::Classic, "Classic"),
+];
+
+pub const RANDOMIZERS: [(Randomizer, &str); 2] = [
+    (Randomizer::SevenBag,   "7-Bag"),
+    (Randomizer::Memoryless, "Memoryless"),
+];
+
+// Every key a game is played with: what it does, its name in the settings file, and its name in the Controls menu.
+// Menus always use the arrow keys, Enter and Escape.
+pub const GAME_KEYS: [(Key, &str, &str); 9] = [
+    (Key::MoveLeft,               "move_left",               "Move Left"),
+    (Key::MoveRight,              "move_right",              "Move Right"),
+    (Key::SoftDrop,               "soft_drop",               "Soft Drop"),
+    (Key::HardDrop,               "hard_drop",               "Hard Drop"),
+    (Key::RotateClockwise,        "rotate_clockwise",        "Rotate Clockwise"),
+    (Key::RotateCounterclockwise, "rotate_counterclockwise", "Rotate Counterclockwise"),
+    (Key::Hold,                   "hold",                    "Hold"),
+    (Key::Pause,                  "pause",                   "Pause"),
+    (Key::Hint,                   "hint",                    "Hint"),
+];
+
+#[derive(Clone)]
+pub struct Controls {
+    // The keys bound to each of GAME_KEYS, in the same order
+    pub bindings: Vec<Vec<Keycode>>,
+}
+
+impl Controls {
+    pub fn new() -> Controls {
+        return Controls {
+            bindings: Vec::from([
+                Vec::from([Keycode::Left]),
+                Vec::from([Keycode::Right]),
+                Vec::from([Keycode::Down]),
+                Vec::from([Keycode::Space]),
+                Vec::from([Keycode::Up, Keycode::Z]),
+                Vec::from([Keycode::X]),
+                Vec::from([Keycode::LShift]),
+                Vec::from([Keycode::P]),
+                Vec::from([Keycode::H]),
+            ]),
+        }
+    }
+
+    pub fn describe(&self, index: usize) -> String {
+        let names: Vec<String> = self.bindings[index].iter().map(|keycode| keycode.to_string()).collect();
+        return if names.is_empty() { String::from("None") } else { names.join(", ") };
+    }
+}
+
+pub struct Settings {
+    pub block_style: BlockStyle,
+    // Only plain ASCII in borders and symbols, for terminals and fonts without box drawing. Set
+    // from the command line, never saved.
+    pub ascii: bool,
+    pub theme: Theme,
+    pub animations: bool,
+    pub line_clear_delay: u128,
+    pub preview_length: usize,
+    pub board_width: usize,
+    pub board_height: usize,
+    // Set with set_stack_visibility, so fading takes its time from fade_seconds
+    pub stack_visibility: StackVisibility,
+    pub fade_seconds: u32,
+    pub flash_stack: bool,
+    pub ghost: bool,
+    // Delayed auto shift and auto repeat rate, in milliseconds
+    pub das: u128,
+    pub arr: u128,
+    // How many times faster than gravity soft drop is
+    pub soft_drop_factor: u32,
+    pub rotation_system: RotationSystem,
+    pub randomizer: Randomizer,
+    // In milliseconds
+    pub lock_delay: u128,
+    pub hold: bool,
+    pub controls: Controls,
+    // Whose games these are, for keeping totals and personal bests apart
+    pub profile: String,
+    // A theme, profile or block style given on the command line lasts for the session only. Each
+    // is kept as (the session's value, the value it replaced), and saving writes back the replaced
+    // value for as long as the setting still holds the session's one.
+    theme_override: Option<(String, String)>,
+    profile_override: Option<(String, String)>,
+    block_style_override: Option<(BlockStyle, BlockStyle)>,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        return Settings {
+            block_style: BlockStyle::detect(),
+            ascii: false,
+            theme: Theme::default(),
+            animations: true,
+            line_clear_delay: LINE_CLEAR_DELAY,
+            preview_length: DEFAULT_PREVIEW_LENGTH,
+            board_width: DEFAULT_BOARD_WIDTH,
+            board_height: DEFAULT_VISIBLE_BOARD_HEIGHT,
+            stack_visibility: StackVisibility::Visible,
+            fade_seconds: DEFAULT_FADE_SECONDS,
+            flash_stack: false,
+            ghost: true,
+            das: DEFAULT_DAS,
+            arr: DEFAULT_ARR,
+            soft_drop_factor: DEFAULT_SOFT_DROP_FACTOR,
+            rotation_system: RotationSystem::Kicks,
+            randomizer: Randomizer::SevenBag,
+            lock_delay: DEFAULT_LOCK_DELAY,
+            hold: true,
+            controls: Controls::new(),
+            profile: DEFAULT_PROFILE.to_string(),
+            theme_override: None,
+            profile_override: None,
+            block_style_override: None,
+        }
+    }
+
+    // A missing file just means the defaults. Anything wrong inside it falls back to the default
+    // one value at a time, so a typo doesn't throw away the rest of the file. The problems are
+    // returned for showing before the game takes over the terminal.
+    pub fn load(path: &Path) -> (Settings, Vec<String>) {
+        return match fs::read_to_string(path) {
+            Ok(text) => {
+                let (settings, problems) = Settings::parse(&text);
+                (settings, problems.into_iter().map(|problem| format!("{}: {}", path.display(), problem)).collect())
+            }
+            Err(_) if !path.exists() => (Settings::new(), Vec::new()),
+            Err(error)               => (Settings::new(), Vec::from([format!("{}: {}", path.display(), error)])),
+        }
+    }
+
+    pub fn parse(text: &str) -> (Settings, Vec<String>) {
+        let mut settings = Settings::new();
+
+        let table: Table = match text.parse() {
+            Ok(table) => table,
+            Err(error) => return (settings, Vec::from([error.to_string()])),
+        };
+
+        let mut reader = SettingsReader { table: &table, known_keys: Vec::new(), problems: Vec::new() };
+
+        if let Some(das) = reader.number("handling", "das", DAS_RANGE) {
+            settings.das = das as u128;
+        }
+        if let Some(arr) = reader.number("handling", "arr", ARR_RANGE) {
+            settings.arr = arr as u128;
+        }
+        if let Some(soft_drop_factor) = reader.number("handling", "sdf", SOFT_DROP_FACTOR_RANGE) {
+            settings.soft_drop_factor = soft_drop_factor as u32;
+        }
+
+        if let Some(name) = reader.value::<String>("visuals", "theme") {
+            match Theme::all().into_iter().find(|theme| theme.name.eq_ignore_ascii_case(&name)) {
+                Some(theme) => settings.theme = theme,
+                None        => reader.problems.push(format!("visuals.theme: there's no theme called '{}'", name)),
+            }
+        }
+        if let Some(block_style) = reader.named("visuals", "block_style", &BLOCK_STYLES) {
+            settings.block_style = block_style;
+        }
+        if let Some(ghost) = reader.value("visuals", "ghost") {
+            settings.ghost = ghost;
+        }
+        if let Some(preview_length) = reader.number("visuals", "preview", 0..=MAX_PREVIEW_LENGTH as i64) {
+            settings.preview_length = preview_length as usize;
+        }
+        if let Some(animations) = reader.value("visuals", "animations") {
+            settings.animations = animations;
+        }
+        if let Some(fade_seconds) = reader.number("visuals", "fade_seconds", FADE_SECONDS_RANGE) {
+            settings.fade_seconds = fade_seconds as u32;
+        }
+        if let Some(stack_visibility) = reader.named("visuals", "stack", &STACK_VISIBILITIES) {
+            settings.set_stack_visibility(stack_visibility);
+        }
+        if let Some(flash_stack) = reader.value("visuals", "flash_stack") {
+            settings.flash_stack = flash_stack;
+        }
+
+        if let Some(rotation_system) = reader.named("rules", "rotation_system", &ROTATION_SYSTEMS) {
+            settings.rotation_system = rotation_system;
+        }
+        if let Some(randomizer) = reader.named("rules", "randomizer", &RANDOMIZERS) {
+            settings.randomizer = randomizer;
+        }
+        if let Some(lock_delay) = reader.number("rules", "lock_delay", LOCK_DELAY_RANGE) {
+            settings.lock_delay = lock_delay as u128;
+        }
+        if let Some(hold) = reader.value("rules", "hold") {
+            settings.hold = hold;
+        }
+        if let Some(board_width) = reader.number("rules", "board_width", MIN_BOARD_WIDTH as i64..=MAX_BOARD_WIDTH as i64) {
+            settings.board_width = board_width as usize;
+        }
+        if let Some(board_height) = reader.number("rules", "board_height", MIN_VISIBLE_BOARD_HEIGHT as i64..=MAX_VISIBLE_BOARD_HEIGHT as i64) {
+            settings.board_height = board_height as usize;
+        }
+
+        for (index, (_, name, _)) in GAME_KEYS.iter().enumerate() {
+            if let Some(keycodes) = reader.keycodes("controls", name) {
+                settings.controls.bindings[index] = keycodes;
+            }
+        }
+
+        if let Some(profile) = reader.value::<String>("player", "profile") {
+            match is_valid_profile_name(&profile) {
+                true  => settings.profile = profile,
+                false => reader.problems.push(format!("player.profile: '{}' isn't a profile name, which is lower case letters, digits and dashes", profile)),
+            }
+        }
+
+        reader.check_for_unknown_keys();
+
+        return (settings, reader.problems);
+    }
+
+    pub fn set_stack_visibility(&mut self, stack_visibility: StackVisibility) {
+        self.stack_visibility = match stack_visibility {
+            StackVisibility::Fading(_) => StackVisibility::Fading(self.fade_seconds as u128 * 1000000),
+            stack_visibility           => stack_visibility,
+        };
+    }
+
+    pub fn set_fade_seconds(&mut self, fade_seconds: u32) {
+        self.fade_seconds = fade_seconds;
+        self.set_stack_visibility(self.stack_visibility);
+    }
+
+    pub fn override_theme(&mut self, theme: Theme) {
+        self.theme_override = Some((theme.name.clone(), self.theme.name.clone()));
+        self.theme = theme;
+    }
+
+    pub fn override_profile(&mut self, profile: String) {
+        self.profile_override = Some((profile.clone(), self.profile.clone()));
+        self.profile = profile;
+    }
+
+    pub fn override_block_style(&mut self, block_style: BlockStyle) {
+        self.block_style_override = Some((block_style, self.block_style));
+        self.block_style = block_style;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(&self.to_table()).map_err(|error| error.to_string())?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| format!("{}: {}", directory.display(), error))?;
+        }
+
+        return fs::write(path, text).map_err(|error| format!("{}: {}", path.display(), error));
+    }
+
+    fn to_table(&self) -> Table {
+        let mut handling = Table::new();
+        handling.insert("das".into(), Value::Integer(self.das as i64));
+        handling.insert("arr".into(), Value::Integer(self.arr as i64));
+        handling.insert("sdf".into(), Value::Integer(self.soft_drop_factor as i64));
+
+        let mut visuals = Table::new();
+        visuals.insert("theme".into(), Value::String(saved_value(&self.theme.name, &self.theme_override)));
+        visuals.insert("block_style".into(), Value::String(name_of(&BLOCK_STYLES, &saved_value(&self.block_style, &self.block_style_override)).into()));
+        visuals.insert("ghost".into(), Value::Boolean(self.ghost));
+        visuals.insert("preview".into(), Value::Integer(self.preview_length as i64));
+        visuals.insert("animations".into(), Value::Boolean(self.animations));
+        visuals.insert("stack".into(), Value::String(stack_visibility_name(&self.stack_visibility).into()));
+        visuals.insert("fade_seconds".into(), Value::Integer(self.fade_seconds as i64));
+        visuals.insert("flash_stack".into(), Value::Boolean(self.flash_stack));
+
+        let mut rules = Table::new();
+        rules.insert("rotation_system".into(), Value::String(name_of(&ROTATION_SYSTEMS, &self.rotation_system).into()));
+        rules.insert("randomizer".into(), Value::String(name_of(&RANDOMIZERS, &self.randomizer).into()));
+        rules.insert("lock_delay".into(), Value::Integer(self.lock_delay as i64));
+        rules.insert("hold".into(), Value::Boolean(self.hold));
+        rules.insert("board_width".into(), Value::Integer(self.board_width as i64));
+        rules.insert("board_height".into(), Value::Integer(self.board_height as i64));
+
+        let mut controls = Table::new();
+        for (index, (_, name, _)) in GAME_KEYS.iter().enumerate() {
+            let keys = self.controls.bindings[index].iter().map(|keycode| Value::String(keycode.to_string())).collect();
+            controls.insert(name.to_string(), Value::Array(keys));
+        }
+
+        let mut player = Table::new();
+        player.insert("profile".into(), Value::String(saved_value(&self.profile, &self.profile_override)));
+
+        let mut table = Table::new();
+        table.insert("player".into(), Value::Table(player));
+        table.insert("handling".into(), Value::Table(handling));
+        table.insert("visuals".into(), Value::Table(visuals));
+        table.insert("rules".into(), Value::Table(rules));
+        table.insert("controls".into(), Value::Table(controls));
+        return table;
+    }
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    return dirs::config_dir().map(|directory| directory.join("terminal-tetris").join("settings.toml"));
+}
+
+// The name a value goes by in the menus and the settings file
+pub fn name_of<T: PartialEq>(names: &[(T, &'static str)], value: &T) -> &'static str {
+    return names.iter().find(|(named_value, _)| named_value == value).map_or(names[0].1, |(_, name)| name);
+}
+
+pub fn stack_visibility_name(stack_visibility: &StackVisibility) -> &'static str {
+    return STACK_VISIBILITIES
+        .iter()
+        .find(|(named_visibility, _)| discriminant(named_visibility) == discriminant(stack_visibility))
+        .map_or(STACK_VISIBILITIES[0].1, |(_, name)| name);
+}
+
+// The value going by `name`, ignoring case
+pub fn named<T: Copy>(names: &[(T, &str)], name: &str) -> Option<T> {
+    return names.iter().find(|(_, known_name)| known_name.eq_ignore_ascii_case(name)).map(|(value, _)| *value);
+}
+
+// What gets saved for a setting the command line may have swapped for the session
+fn saved_value<T: Clone + PartialEq>(value: &T, session_override: &Option<(T, T)>) -> T {
+    return match session_override {
+        Some((session_value, replaced_value)) if session_value == value => replaced_value.clone(),
+        _ => value.clone(),
+    };
+}
+
+// Reads values out of the settings file one at a time, noting down whatever's wrong with them
+struct SettingsReader<'a> {
+    table: &'a Table,
+    known_keys: Vec<(&'static str, &'static str)>,
+    problems: Vec<String>,
+}
+
+impl SettingsReader<'_> {
+    fn value<T: DeserializeOwned>(&mut self, section: &'static str, key: &'static str) -> Option<T> {
+        self.known_keys.push((section, key));
+        let value = self.table.get(section)?.get(key)?;
+
+        return match value.clone().try_into() {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.problems.push(format!("{}.{}: {}", section, key, error.to_string().trim()));
+                None
+            }
+        }
+    }
+
+    fn number(&mut self, section: &'static str, key: &'static str, range: RangeInclusive<i64>) -> Option<i64> {
+        let number: i64 = self.value(section, key)?;
+
+        if !range.contains(&number) {
+            self.problems.push(format!("{}.{}: {} isn't between {} and {}", section, key, number, range.start(), range.end()));
+            return None;
+        }
+
+        return Some(number);
+    }
+
+    fn named<T: Copy>(&mut self, section: &'static str, key: &'static str, names: &[(T, &str)]) -> Option<T> {
+        let name: String = self.value(section, key)?;
+
+        let value = named(names, &name);
+        if value.is_none() {
+            let known_names: Vec<&str> = names.iter().map(|(_, known_name)| *known_name).collect();
+            self.problems.push(format!("{}.{}: '{}' isn't one of {}", section, key, name, known_names.join(", ")));
+        }
+
+        return value;
+    }
+
+    fn keycodes(&mut self, section: &'static str, key: &'static str) -> Option<Vec<Keycode>> {
+        let names: Vec<String> = self.value(section, key)?;
+
+        let keycodes: Result<Vec<Keycode>, String> = names
+            .iter()
+            .map(|name| name.parse().map_err(|_| format!("{}.{}: there's no key called '{}'", section, key, name)))
+            .collect();
+
+        return match keycodes {
+            Ok(keycodes) => Some(keycodes),
+            Err(problem) => {
+                self.problems.push(problem);
+                None
+            }
+        }
+    }
+
+    fn check_for_unknown_keys(&mut self) {
+        for (section, values) in self.table {
+            let keys = match values.as_table() {
+                Some(keys) => keys,
+                None => {
+                    self.problems.push(format!("{}: unknown setting", section));
+                    continue;
+                }
+            };
+
+            for key in keys.keys() {
+                let is_known = self.known_keys.iter().any(|(known_section, known_key)| known_section == section && known_key == key);
+                if !is_known {
+                    self.problems.push(format!("{}.{}: unknown setting", section, key));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_FILE: &str = r#"
+        [player]
+        profile = "alex"
+
+        [handling]
+        das = 120
+        arr = 0
+        sdf = 40
+
+        [visuals]
+        theme = "NES"
+        block_style = "Monochrome"
+        ghost = false
+        preview = 6
+        animations = false
+        stack = "Fading"
+        fade_seconds = 12
+        flash_stack = true
+
+        [rules]
+        rotation_system = "Classic"
+        randomizer = "Memoryless"
+        lock_delay = 0
+        hold = false
+        board_width = 12
+        board_height = 30
+
+        [controls]
+        move_left = ["A", "Left"]
+        hard_drop = []
+    "#;
+
+    #[test]
+    fn every_setting_is_read() {
+        let (settings, problems) = Settings::parse(FULL_FILE);
+
+        assert_eq!(problems, Vec::<String>::new());
+        assert_eq!(settings.profile, "alex");
+        assert_eq!((settings.das, settings.arr, settings.soft_drop_factor), (120, 0, 40));
+        assert_eq!(settings.theme.name, "NES");
+        assert_eq!(settings.block_style, BlockStyle::Monochrome);
+        assert_eq!((settings.ghost, settings.preview_length, settings.animations), (false, 6, false));
+        assert_eq!(settings.stack_visibility, StackVisibility::Fading(12000000));
+        assert!(settings.flash_stack);
+        assert_eq!(settings.rotation_system, RotationSystem::Classic);
+        assert_eq!(settings.randomizer, Randomizer::Memoryless);
+        assert_eq!((settings.lock_delay, settings.hold), (0, false));
+        assert_eq!((settings.board_width, settings.board_height), (12, 30));
+        assert_eq!(settings.controls.bindings[0], Vec::from([Keycode::A, Keycode::Left]));
+        assert_eq!(settings.controls.bindings[3], Vec::new());
+        assert_eq!(settings.controls.bindings[1], Controls::new().bindings[1]);
+    }
+
+    #[test]
+    fn saved_settings_read_back_the_same() {
+        let (settings, _) = Settings::parse(FULL_FILE);
+        let text = toml::to_string(&settings.to_table()).unwrap();
+        let (read_back, problems) = Settings::parse(&text);
+
+        assert_eq!(problems, Vec::<String>::new());
+        assert_eq!(read_back.to_table(), settings.to_table());
+    }
+
+    #[test]
+    fn missing_settings_keep_their_defaults() {
+        let (settings, problems) = Settings::parse("[handling]\ndas = 100\n");
+        let defaults = Settings::new();
+
+        assert_eq!(problems, Vec::<String>::new());
+        assert_eq!(settings.das, 100);
+        assert_eq!(settings.arr, defaults.arr);
+        assert_eq!(settings.theme.name, defaults.theme.name);
+        assert_eq!(settings.block_style, defaults.block_style);
+        assert_eq!(settings.stack_visibility, defaults.stack_visibility);
+        assert_eq!(settings.rotation_system, defaults.rotation_system);
+        assert_eq!(settings.profile, defaults.profile);
+        assert_eq!(settings.controls.bindings, defaults.controls.bindings);
+    }
+
+    #[test]
+    fn invalid_settings_fall_back_one_at_a_time() {
+        let (settings, problems) = Settings::parse(r#"
+            [handling]
+            das = 5000
+            arr = "fast"
+            sdf = 10
+
+            [visuals]
+            theme = "Neon"
+            block_style = "Sepia"
+            colour = "red"
+
+            [rules]
+            rotation_system = "Spinny"
+            board_width = 2
+
+            [controls]
+            move_left = ["NoSuchKey"]
+
+            [player]
+            profile = "Not A Name"
+
+            [sound]
+            volume = 3
+        "#);
+        let defaults = Settings::new();
+
+        assert_eq!(settings.soft_drop_factor, 10);
+        assert_eq!((settings.das, settings.arr), (defaults.das, defaults.arr));
+        assert_eq!(settings.theme.name, defaults.theme.name);
+        assert_eq!(settings.block_style, defaults.block_style);
+        assert_eq!(settings.rotation_system, defaults.rotation_system);
+        assert_eq!(settings.board_width, defaults.board_width);
+        assert_eq!(settings.controls.bindings[0], defaults.controls.bindings[0]);
+        assert_eq!(settings.profile, defaults.profile);
+
+        let problem_keys: Vec<&str> = problems.iter().map(|problem| problem.split(':').next().unwrap()).collect();
+        assert_eq!(problem_keys, Vec::from([
+            "handling.das",
+            "handling.arr",
+            "visuals.theme",
+            "visuals.block_style",
+            "rules.rotation_system",
+            "rules.board_width",
+            "controls.move_left",
+            "player.profile",
+            "sound.volume",
+            "visuals.colour",
+        ]));
+    }
+
+    #[test]
+    fn command_line_overrides_are_not_saved() {
+        let (mut settings, _) = Settings::parse(FULL_FILE);
+        let session_theme = Theme::all().into_iter().find(|theme| theme.name == "Classic").unwrap();
+        settings.override_theme(session_theme);
+        settings.override_profile(String::from("guest"));
+        settings.override_block_style(BlockStyle::Emoji);
+
+        let saved = settings.to_table();
+        assert_eq!(saved["visuals"]["theme"].as_str(), Some("NES"));
+        assert_eq!(saved["visuals"]["block_style"].as_str(), Some("Monochrome"));
+        assert_eq!(saved["player"]["profile"].as_str(), Some("alex"));
+
+        // Choosing something else in the menus is saved as usual
+        settings.theme = Theme::all().into_iter().find(|theme| theme.name == "Guideline").unwrap();
+        settings.profile = String::from("sam");
+        settings.block_style = BlockStyle::Ansi16;
+
+        let saved = settings.to_table();
+        assert_eq!(saved["visuals"]["theme"].as_str(), Some("Guideline"));
+        assert_eq!(saved["visuals"]["block_style"].as_str(), Some("16 Colors"));
+        assert_eq!(saved["player"]["profile"].as_str(), Some("sam"));
+    }
+
+    #[test]
+    fn unreadable_files_give_the_defaults() {
+        let (settings, problems) = Settings::parse("[handling\ndas = 100");
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(settings.das, Settings::new().das);
+    }
+}
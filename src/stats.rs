@@ -0,0 +1,128 @@
+// Numbers about how a game was played rather than how it scored, for the results screen and the
+// history file
+use tetris_core::piece::PieceType;
+use tetris_core::tetris::{TSpin, Tetris};
+use crate::hud::Hud;
+
+// In the order of PieceType
+pub const PIECE_NAMES: [&str; 7] = ["O", "I", "L", "J", "S", "Z", "T"];
+
+// Every kind of lock worth counting, and what it's called
+pub const CLEAR_KINDS: [(TSpin, u8, &str); 11] = [
+    (TSpin::None, 1, "Single"),
+    (TSpin::None, 2, "Double"),
+    (TSpin::None, 3, "Triple"),
+    (TSpin::None, 4, "Tetris"),
+    (TSpin::Mini, 0, "T-Spin Mini"),
+    (TSpin::Mini, 1, "T-Spin Mini Single"),
+    (TSpin::Mini, 2, "T-Spin Mini Double"),
+    (TSpin::Full, 0, "T-Spin"),
+    (TSpin::Full, 1, "T-Spin Single"),
+    (TSpin::Full, 2, "T-Spin Double"),
+    (TSpin::Full, 3, "T-Spin Triple"),
+];
+
+pub struct GameStats {
+    pub score: u32,
+    pub lines: u32,
+    // In microseconds, not counting pauses
+    pub time: u128,
+    pub pieces: u32,
+    // Indexed like PIECE_NAMES
+    pub pieces_by_type: [u32; 7],
+    // Indexed like CLEAR_KINDS
+    pub clears: [u32; 11],
+    // Presses, not repeats, of the keys that move the piece or hold it
+    pub key_presses: u32,
+    pub holds: u32,
+    pub finesse_faults: u32,
+    // Garbage lines the clears were worth, before any cancelled incoming garbage
+    pub attack: u32,
+    pub max_combo: u32,
+    pub max_back_to_back: u32,
+    pub perfect_clears: u32,
+}
+
+impl GameStats {
+    pub fn new() -> GameStats {
+        return GameStats {
+            score: 0,
+            lines: 0,
+            time: 0,
+            pieces: 0,
+            pieces_by_type: [0; 7],
+            clears: [0; 11],
+            key_presses: 0,
+            holds: 0,
+            finesse_faults: 0,
+            attack: 0,
+            max_combo: 0,
+            max_back_to_back: 0,
+            perfect_clears: 0,
+        }
+    }
+
+    // Catches up with any piece that locked in `tetris` since the last call. The HUD has to have
+    // seen it first, since it keeps the score.
+    pub fn observe(&mut self, tetris: &Tetris, hud: &Hud) {
+        self.score = hud.score;
+        self.lines = hud.lines;
+        self.time = hud.time;
+
+        if tetris.pieces_locked() == self.pieces {
+            return;
+        }
+
+        self.pieces = tetris.pieces_locked();
+
+        if let Some(piece) = tetris.last_locked_piece() {
+            self.pieces_by_type[PieceType::from_block_type(&piece.block_type) as usize] += 1;
+        }
+
+        let lock_result = match tetris.last_lock_result() {
+            Some(lock_result) => lock_result,
+            None => return,
+        };
+
+        let clear_kind = CLEAR_KINDS.iter().position(|(t_spin, lines, _)| *t_spin == lock_result.t_spin && *lines == lock_result.cleared_lines);
+        if let Some(clear_kind) = clear_kind {
+            self.clears[clear_kind] += 1;
+        }
+
+        self.max_combo = self.max_combo.max(lock_result.combo.unwrap_or(0));
+        self.max_back_to_back = self.max_back_to_back.max(lock_result.back_to_back.unwrap_or(0));
+        if lock_result.is_perfect_clear {
+            self.perfect_clears += 1;
+        }
+
+        self.attack += lock_result.attack;
+    }
+
+    // The score and time keep changing after the last piece locks, e.g. while the game is paused
+    // on the way out, so they're brought up to date once the game is over
+    pub fn finish(&mut self, hud: &Hud) {
+        self.score = hud.score;
+        self.lines = hud.lines;
+        self.time = hud.time;
+    }
+
+    pub fn pieces_per_second(&self) -> f64 {
+        return per(self.pieces as f64, self.time as f64 / 1000000.0);
+    }
+
+    pub fn keys_per_piece(&self) -> f64 {
+        return per(self.key_presses as f64, self.pieces as f64);
+    }
+
+    pub fn attack_per_minute(&self) -> f64 {
+        return per(self.attack as f64, self.time as f64 / 60000000.0);
+    }
+}
+
+fn per(amount: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        return 0.0;
+    }
+
+    return amount / total;
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crossterm::style::Color;
+use serde::Deserialize;
+use tetris_core::piece::BlockType;
+use unicode_width::UnicodeWidthStr;
+
+// Every block is drawn two columns wide, so glyphs have to be exactly that
+const GLYPH_WIDTH: usize = 2;
+
+const BUILT_IN_THEMES: [&str; 5] = [
+    include_str!("../themes/classic.toml"),
+    include_str!("../themes/guideline.toml"),
+    include_str!("../themes/nes.toml"),
+    include_str!("../themes/high-contrast.toml"),
+    include_str!("../themes/colour-blind-safe.toml"),
+];
+
+#[derive(Clone)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    pub name: String,
+    pub border: ThemeColor,
+    pub hud: ThemeColor,
+    pub menu_highlight: ThemeColor,
+    pub blocks: ThemeBlocks,
+}
+
+#[derive(Clone)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeBlocks {
+    o: BlockAppearance,
+    i: BlockAppearance,
+    l: BlockAppearance,
+    j: BlockAppearance,
+    s: BlockAppearance,
+    z: BlockAppearance,
+    t: BlockAppearance,
+    garbage: BlockAppearance,
+    ghost: BlockAppearance,
+    outline: BlockAppearance,
+    target: BlockAppearance,
+    hint: BlockAppearance,
+    alternative_hint: BlockAppearance,
+    empty: BlockAppearance,
+}
+
+#[derive(Clone)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockAppearance {
+    pub glyph: String,
+    pub fg: Option<ThemeColor>,
+    pub bg: Option<ThemeColor>,
+}
+
+// Written in theme files as "#rrggbb", a colour name like "dark_red", or a 256-colour palette index like "208"
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct ThemeColor(pub Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<ThemeColor, String> {
+        if let Some(hex) = text.strip_prefix('#') {
+            let is_valid_hex = hex.len() == 6 && hex.chars().all(|digit| digit.is_ascii_hexdigit());
+            if !is_valid_hex {
+                return Err(format!("'{}' is not a #rrggbb colour", text));
+            }
+
+            let channel = |start: usize| u8::from_str_radix(&hex[start..start + 2], 16).unwrap();
+            return Ok(ThemeColor(Color::Rgb { r: channel(0), g: channel(2), b: channel(4) }));
+        }
+
+        if let Ok(index) = text.parse::<u8>() {
+            return Ok(ThemeColor(Color::AnsiValue(index)));
+        }
+
+        return match Color::try_from(text.as_str()) {
+            Ok(color) => Ok(ThemeColor(color)),
+            Err(_) => Err(format!("'{}' is not a colour", text)),
+        }
+    }
+}
+
+impl ThemeBlocks {
+    pub fn get(&self, block_type: &BlockType) -> &BlockAppearance {
+        return match block_type {
+            BlockType::O               => &self.o,
+            BlockType::I               => &self.i,
+            BlockType::L               => &self.l,
+            BlockType::J               => &self.j,
+            BlockType::S               => &self.s,
+            BlockType::Z               => &self.z,
+            BlockType::T               => &self.t,
+            BlockType::Garbage         => &self.garbage,
+            BlockType::Ghost           => &self.ghost,
+            BlockType::Outline         => &self.outline,
+            BlockType::Target          => &self.target,
+            BlockType::Hint            => &self.hint,
+            BlockType::AlternativeHint => &self.alternative_hint,
+            BlockType::Empty           => &self.empty,
+        }
+    }
+
+    fn all(&self) -> [(&'static str, &BlockAppearance); 14] {
+        return [
+            ("o", &self.o),
+            ("i", &self.i),
+            ("l", &self.l),
+            ("j", &self.j),
+            ("s", &self.s),
+            ("z", &self.z),
+            ("t", &self.t),
+            ("garbage", &self.garbage),
+            ("ghost", &self.ghost),
+            ("outline", &self.outline),
+            ("target", &self.target),
+            ("hint", &self.hint),
+            ("alternative_hint", &self.alternative_hint),
+            ("empty", &self.empty),
+        ];
+    }
+}
+
+impl Theme {
+    pub fn parse(text: &str) -> Result<Theme, String> {
+        let theme: Theme = toml::from_str(text).map_err(|error| error.to_string())?;
+
+        for (name, appearance) in theme.blocks.all() {
+            if appearance.glyph.width() != GLYPH_WIDTH {
+                return Err(format!("the glyph for '{}' has to be {} columns wide", name, GLYPH_WIDTH));
+            }
+        }
+
+        return Ok(theme);
+    }
+
+    pub fn load(path: &Path) -> Result<Theme, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        return Theme::parse(&text).map_err(|error| format!("{}: {}", path.display(), error));
+    }
+
+    pub fn built_in() -> Vec<Theme> {
+        return BUILT_IN_THEMES
+            .iter()
+            .map(|text| Theme::parse(text).expect("built-in themes are valid"))
+            .collect();
+    }
+
+    // The built-in themes followed by any *.toml files in the user's theme directory.
+    // Files that fail to load are left out, since there's nowhere to show the error mid-game.
+    pub fn all() -> Vec<Theme> {
+        let mut themes = Theme::built_in();
+
+        let entries = match user_theme_directory().map(fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return themes,
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+            .collect();
+        paths.sort();
+
+        themes.extend(paths.iter().filter_map(|path| Theme::load(path).ok()));
+
+        return themes;
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        return Theme::built_in().remove(0);
+    }
+}
+
+pub fn user_theme_directory() -> Option<PathBuf> {
+    return dirs::config_dir().map(|directory| directory.join("terminal-tetris").join("themes"));
+}
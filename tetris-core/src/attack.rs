@@ -0,0 +1,116 @@
+//! How many garbage lines a clear sends to an opponent.
+//!
+//! [`crate::tetris::Tetris`] works out the attack of every lock with its [`AttackTable`],
+//! cancels it against any garbage waiting to come up its own board, and keeps the rest for the
+//! frontend to pass on with [`crate::tetris::Tetris::take_outgoing_garbage`].
+
+use crate::tetris::{LockResult, TSpin};
+
+/// The garbage each kind of clear sends, and the bonuses on top. The defaults are the usual
+/// guideline amounts.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct AttackTable {
+    /// Lines sent for clearing one to four lines without a T-spin.
+    pub lines: [u32; 4],
+    /// Lines sent for T-spin minis clearing none to two lines.
+    pub t_spin_mini: [u32; 3],
+    /// Lines sent for T-spins clearing none to three lines.
+    pub t_spin: [u32; 4],
+    /// Added to difficult clears that carry on a back-to-back chain. See [`LockResult::is_back_to_back`].
+    pub back_to_back: u32,
+    /// Added to clears by their combo, starting at combo zero. Longer combos get the last entry.
+    pub combo: Vec<u32>,
+    /// Added to clears that leave the board empty.
+    pub perfect_clear: u32,
+}
+
+impl Default for AttackTable {
+    fn default() -> AttackTable {
+        return AttackTable {
+            lines: [0, 1, 2, 4],
+            t_spin_mini: [0, 0, 1],
+            t_spin: [0, 2, 4, 6],
+            back_to_back: 1,
+            combo: Vec::from([0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5]),
+            perfect_clear: 10,
+        }
+    }
+}
+
+impl AttackTable {
+    /// The lines a lock sends, before any are cancelled against incoming garbage. Bonuses only
+    /// apply to locks that clear lines.
+    pub fn attack(&self, lock_result: &LockResult) -> u32 {
+        let lines = lock_result.cleared_lines as usize;
+
+        // Minis clearing more than two lines send as much as doubles, as they score
+        let clear_attack = match (lock_result.t_spin, lines) {
+            (TSpin::None, 0)     => 0,
+            (TSpin::None, lines) => self.lines[lines - 1],
+            (TSpin::Mini, lines) => self.t_spin_mini[lines.min(2)],
+            (TSpin::Full, lines) => self.t_spin[lines],
+        };
+
+        if lines == 0 {
+            return clear_attack;
+        }
+
+        let back_to_back_attack = if lock_result.is_back_to_back() { self.back_to_back } else { 0 };
+        let combo_attack = lock_result.combo
+            .and_then(|combo| self.combo.get(combo as usize).or(self.combo.last()))
+            .copied()
+            .unwrap_or(0);
+        let perfect_clear_attack = if lock_result.is_perfect_clear { self.perfect_clear } else { 0 };
+
+        return clear_attack + back_to_back_attack + combo_attack + perfect_clear_attack;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_result(cleared_lines: u8, t_spin: TSpin, combo: Option<u32>, back_to_back: Option<u32>) -> LockResult {
+        return LockResult {
+            cleared_lines,
+            cleared_rows: Vec::new(),
+            t_spin,
+            combo,
+            back_to_back,
+            is_perfect_clear: false,
+            attack: 0,
+            garbage_sent: 0,
+        }
+    }
+
+    #[test]
+    fn combos_past_the_end_of_the_table_get_its_last_entry() {
+        let attack_table = AttackTable::default();
+
+        assert_eq!(attack_table.attack(&lock_result(1, TSpin::None, Some(2), None)), 1);
+        assert_eq!(attack_table.attack(&lock_result(1, TSpin::None, Some(11), None)), 5);
+        assert_eq!(attack_table.attack(&lock_result(1, TSpin::None, Some(50), None)), 5);
+        assert_eq!(attack_table.attack(&lock_result(2, TSpin::None, Some(50), None)), 6);
+    }
+
+    #[test]
+    fn bonuses_only_apply_to_locks_that_clear_lines() {
+        let attack_table = AttackTable { combo: Vec::from([3]), ..AttackTable::default() };
+
+        assert_eq!(attack_table.attack(&lock_result(0, TSpin::None, None, Some(3))), 0);
+        assert_eq!(attack_table.attack(&lock_result(0, TSpin::Full, None, Some(3))), 0);
+        assert_eq!(attack_table.attack(&lock_result(4, TSpin::None, Some(0), Some(3))), 4 + 1 + 3);
+    }
+
+    #[test]
+    fn t_spins_send_by_their_own_tables() {
+        let attack_table = AttackTable::default();
+
+        assert_eq!(attack_table.attack(&lock_result(2, TSpin::Full, Some(0), Some(0))), 4);
+        assert_eq!(attack_table.attack(&lock_result(3, TSpin::Full, Some(0), Some(1))), 7);
+        assert_eq!(attack_table.attack(&lock_result(1, TSpin::Mini, Some(0), Some(0))), 0);
+        assert_eq!(attack_table.attack(&lock_result(4, TSpin::Mini, Some(0), Some(0))), 1);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use crate::movegen::Placement;
+use crate::piece::BlockType;
+use crate::tetris::{Board, GameSnapshot, Input, MoveOutcome, Tetris};
+
+/// How many upcoming pieces a [`BotPlayer`] shows its bot.
+const BOT_PREVIEW_LENGTH: usize = 5;
+
+/// Something that decides what to do with the active piece.
+pub trait Bot {
+    /// Picks a move for the active piece in `snapshot`.
+    fn choose_move(&mut self, snapshot: &GameSnapshot) -> BotMove;
+}
+
+/// A decision made by a [`Bot`].
+pub enum BotMove {
+    /// Put the piece at this placement, using the placement's own inputs.
+    Placement(Placement),
+    /// Perform exactly these inputs.
+    Inputs(Vec<Input>),
+}
+
+impl BotMove {
+    /// The inputs needed to carry out the move.
+    pub fn into_inputs(self) -> Vec<Input> {
+        return match self {
+            BotMove::Placement(placement) => placement.inputs,
+            BotMove::Inputs(inputs) => inputs,
+        }
+    }
+}
+
+/// How much each board feature counts towards a placement's score. Higher scores are better.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+pub struct Weights {
+    /// Sum of all column heights.
+    pub aggregate_height: f64,
+    /// Empty cells with a filled cell somewhere above them.
+    pub holes: f64,
+    /// Sum of height differences between neighbouring columns.
+    pub bumpiness: f64,
+    /// Sum of the depths of single-column wells.
+    pub wells: f64,
+    /// Lines cleared by the placement.
+    pub cleared_lines: f64,
+}
+
+impl Weights {
+    /// Weights tuned for the classic one-piece-lookahead heuristic player.
+    pub fn classic() -> Weights {
+        return Weights {
+            aggregate_height: -0.510066,
+            holes: -0.35663,
+            bumpiness: -0.184483,
+            wells: -0.05,
+            cleared_lines: 0.760666,
+        }
+    }
+}
+
+/// A bot that tries every reachable placement of the active piece, and of the piece it could
+/// hold instead, and keeps the one whose resulting board scores best according to its [`Weights`].
+pub struct HeuristicBot {
+    /// The weights used to score boards.
+    pub weights: Weights,
+}
+
+impl HeuristicBot {
+    /// Creates a bot using [`Weights::classic`].
+    pub fn new() -> HeuristicBot {
+        return HeuristicBot { weights: Weights::classic() };
+    }
+
+    /// Scores the board that results from locking `placement` on `board`.
+    pub fn evaluate(&self, board: &Board, placement: &Placement) -> f64 {
+        let mut board = board.clone();
+        Tetris::stick_piece_to_board(&placement.piece, &mut board);
+        let cleared_lines = Tetris::clear_full_lines(&mut board);
+
+        let features = BoardFeatures::from_board(&board);
+
+        return self.weights.aggregate_height * features.aggregate_height as f64
+            + self.weights.holes * features.holes as f64
+            + self.weights.bumpiness * features.bumpiness as f64
+            + self.weights.wells * features.wells as f64
+            + self.weights.cleared_lines * cleared_lines as f64;
+    }
+
+    /// Scores each of `placements` on `board` and sorts them best first.
+    pub fn rank_placements(&self, placements: Vec<Placement>, board: &Board) -> Vec<(f64, Placement)> {
+        let mut ranked_placements: Vec<(f64, Placement)> = placements
+            .into_iter()
+            .map(|placement| (self.evaluate(board, &placement), placement))
+            .collect();
+
+        ranked_placements.sort_by(|(score_a, _), (score_b, _)| score_b.total_cmp(score_a));
+
+        return ranked_placements;
+    }
+}
+
+impl Default for HeuristicBot {
+    fn default() -> HeuristicBot {
+        return HeuristicBot::new();
+    }
+}
+
+impl Bot for HeuristicBot {
+    fn choose_move(&mut self, snapshot: &GameSnapshot) -> BotMove {
+        let best_placement = self.rank_placements(snapshot.reachable_placements(), &snapshot.board)
+            .into_iter()
+            .next();
+
+        return match best_placement {
+            Some((_, placement)) => BotMove::Placement(placement),
+            None => BotMove::Inputs(Vec::from([Input::HardDrop])),
+        }
+    }
+}
+
+/// Measurements of a board used by [`HeuristicBot`].
+#[derive(Debug)]
+pub struct BoardFeatures {
+    /// Sum of all column heights.
+    pub aggregate_height: u32,
+    /// Empty cells with a filled cell somewhere above them.
+    pub holes: u32,
+    /// Sum of height differences between neighbouring columns.
+    pub bumpiness: u32,
+    /// Sum of the depths of single-column wells. The walls count as infinitely tall.
+    pub wells: u32,
+}
+
+impl BoardFeatures {
+    /// Measures `board`.
+    pub fn from_board(board: &Board) -> BoardFeatures {
+        let height = board.blocks.len();
+        let width = board.blocks[0].len();
+
+        let mut column_heights: Vec<u32> = vec![0; width];
+        let mut holes: u32 = 0;
+
+        for (column_index, column_height) in column_heights.iter_mut().enumerate() {
+            let mut found_top = false;
+            for row_index in 0..height {
+                let is_empty = board.blocks[row_index][column_index].block_type == BlockType::Empty;
+                if !is_empty && !found_top {
+                    found_top = true;
+                    *column_height = (height - row_index) as u32;
+                } else if is_empty && found_top {
+                    holes += 1;
+                }
+            }
+        }
+
+        let bumpiness = column_heights
+            .windows(2)
+            .map(|pair| pair[0].abs_diff(pair[1]))
+            .sum();
+
+        let mut wells: u32 = 0;
+        for column_index in 0..width {
+            let left = if column_index == 0 { u32::MAX } else { column_heights[column_index - 1] };
+            let right = if column_index == width - 1 { u32::MAX } else { column_heights[column_index + 1] };
+            let shortest_neighbour = left.min(right);
+            if shortest_neighbour != u32::MAX && shortest_neighbour > column_heights[column_index] {
+                wells += shortest_neighbour - column_heights[column_index];
+            }
+        }
+
+        return BoardFeatures {
+            aggregate_height: column_heights.iter().sum(),
+            holes,
+            bumpiness,
+            wells,
+        }
+    }
+}
+
+/// Drives a [`Tetris`] game with a [`Bot`], performing one input every `input_interval` microseconds.
+pub struct BotPlayer<B: Bot> {
+    bot: B,
+    pending_inputs: VecDeque<Input>,
+    input_interval: u128,
+    time_since_last_input: u128,
+}
+
+impl<B: Bot> BotPlayer<B> {
+    /// Creates a player that performs one input every `input_interval` microseconds.
+    pub fn new(bot: B, input_interval: u128) -> BotPlayer<B> {
+        return BotPlayer {
+            bot,
+            pending_inputs: VecDeque::new(),
+            input_interval,
+            time_since_last_input: 0,
+        }
+    }
+
+    /// Forgets any planned inputs, e.g. when starting a new game.
+    pub fn reset(&mut self) {
+        self.pending_inputs.clear();
+        self.time_since_last_input = 0;
+    }
+
+    /// Advances time by `delta_time` and performs whichever inputs are due.
+    ///
+    /// Returns `None` if no input was performed, otherwise the most significant outcome.
+    pub fn update(&mut self, tetris: &mut Tetris, delta_time: &u128) -> Option<MoveOutcome> {
+        self.time_since_last_input += delta_time;
+
+        let mut outcome: Option<MoveOutcome> = None;
+
+        while self.time_since_last_input > self.input_interval {
+            self.time_since_last_input -= self.input_interval;
+
+            if self.pending_inputs.is_empty() {
+                let snapshot = tetris.snapshot(BOT_PREVIEW_LENGTH);
+                self.pending_inputs.extend(self.bot.choose_move(&snapshot).into_inputs());
+            }
+
+            if let Some(input) = self.pending_inputs.pop_front() {
+                let input_outcome = tetris.apply_input(input);
+
+                match input_outcome {
+                    MoveOutcome::GameOver => return Some(MoveOutcome::GameOver),
+                    MoveOutcome::NothingSpecial if outcome.is_some() => {},
+                    _ => outcome = Some(input_outcome),
+                }
+            }
+        }
+
+        return outcome;
+    }
+}
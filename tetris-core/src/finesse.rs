@@ -0,0 +1,375 @@
+use std::collections::VecDeque;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::movegen::{find_placements_by_cost, Placement};
+use crate::piece::{BlockType, Piece};
+use crate::tetris::{Board, Input, RotationSystem, Tetris};
+
+/// How many of the most recent pieces [`FinesseTrainer::recent_accuracy`] looks at.
+pub const RECENT_PIECES_WINDOW: usize = 20;
+
+/// How many key presses a sequence of inputs costs for finesse purposes.
+///
+/// Only movement and rotation count. Drops and hold are free, since every placement ends
+/// with a hard drop and soft drops are a matter of speed rather than finesse.
+pub fn finesse_cost(inputs: &[Input]) -> u32 {
+    return inputs
+        .iter()
+        .filter(|input| !matches!(input, Input::SoftDrop | Input::SonicDrop | Input::HardDrop | Input::Hold))
+        .count() as u32;
+}
+
+/// The sequence of inputs with the lowest [`finesse_cost`] that takes `piece` to `target` on
+/// `board`, using the engine's own movement and rotation rules, or `None` if `target` can't be
+/// reached. Of the sequences that cost the same, it's the shortest.
+pub fn minimal_inputs(board: &Board, piece: &Piece, target: &Piece, rotation_system: RotationSystem) -> Option<Vec<Input>> {
+    let target_placement = Placement { piece: *target, inputs: Vec::new() };
+
+    return finesse_placements(board, piece, rotation_system)
+        .into_iter()
+        .find(|placement| placement.covers_same_cells(&target_placement))
+        .map(|placement| placement.inputs);
+}
+
+// Every placement, reached with as few key presses as finesse counts them
+fn finesse_placements(board: &Board, piece: &Piece, rotation_system: RotationSystem) -> Vec<Placement> {
+    return find_placements_by_cost(board, piece, rotation_system, |input| finesse_cost(&[input]));
+}
+
+/// How a single piece was placed, compared to the minimum.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct FinesseResult {
+    /// The shortest inputs that reach the placement the player chose.
+    pub expected_inputs: Vec<Input>,
+    /// How many movement and rotation keys the player actually pressed.
+    pub key_presses: u32,
+    /// Key presses beyond the minimum. Zero means the piece was placed with perfect finesse.
+    pub faults: u32,
+}
+
+/// Counts the player's key presses for each piece and compares them against the minimum.
+///
+/// Call [`FinesseTracker::start_piece`] whenever a new piece becomes active (after spawning or
+/// holding), [`FinesseTracker::record_key_press`] for every fresh movement or rotation key
+/// press (not auto-repeats), and [`FinesseTracker::finish_piece`] once the piece has locked.
+pub struct FinesseTracker {
+    start: Option<(Board, Piece, RotationSystem)>,
+    key_presses: u32,
+    /// Pieces judged so far.
+    pub pieces: u32,
+    /// Total faults across all judged pieces.
+    pub faults: u32,
+}
+
+impl FinesseTracker {
+    /// Creates a tracker with no pieces judged.
+    pub fn new() -> FinesseTracker {
+        return FinesseTracker {
+            start: None,
+            key_presses: 0,
+            pieces: 0,
+            faults: 0,
+        }
+    }
+
+    /// Starts counting for the game's active piece.
+    pub fn start_piece(&mut self, tetris: &Tetris) {
+        self.start = Some((tetris.board().clone(), *tetris.active_piece(), tetris.rotation_system()));
+        self.key_presses = 0;
+    }
+
+    /// Counts one movement or rotation key press towards the current piece.
+    pub fn record_key_press(&mut self) {
+        self.key_presses += 1;
+    }
+
+    /// Judges the piece that just locked in `tetris`.
+    pub fn finish_piece(&mut self, tetris: &Tetris) -> Option<FinesseResult> {
+        let (board, piece, rotation_system) = self.start.take()?;
+        let locked_piece = tetris.last_locked_piece()?;
+        let expected_inputs = minimal_inputs(&board, &piece, locked_piece, rotation_system)?;
+
+        let faults = self.key_presses.saturating_sub(finesse_cost(&expected_inputs));
+        self.pieces += 1;
+        self.faults += faults;
+
+        return Some(FinesseResult {
+            expected_inputs,
+            key_presses: self.key_presses,
+            faults,
+        });
+    }
+}
+
+impl Default for FinesseTracker {
+    fn default() -> FinesseTracker {
+        return FinesseTracker::new();
+    }
+}
+
+/// The verdict on one piece placed in the trainer.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct TrainerVerdict {
+    /// Whether the piece locked exactly on the target.
+    pub hit_target: bool,
+    /// The finesse of the placement, if it could be judged.
+    pub finesse: Option<FinesseResult>,
+    /// The minimal inputs for the target, so the player can see what they should have done.
+    pub target_inputs: Vec<Input>,
+}
+
+impl TrainerVerdict {
+    /// Whether the piece hit the target with perfect finesse.
+    pub fn is_clean(&self) -> bool {
+        return self.hit_target && self.finesse.as_ref().is_some_and(|finesse| finesse.faults == 0);
+    }
+}
+
+/// Drills finesse by asking for random placements of each piece on an empty board.
+///
+/// Results can carry over from earlier sessions, so accuracy can be followed over time. See
+/// [`FinesseTrainer::with_results`].
+pub struct FinesseTrainer {
+    /// Where the active piece should go.
+    pub target: Option<Placement>,
+    /// The verdict on the previous piece.
+    pub last_verdict: Option<TrainerVerdict>,
+    pieces: usize,
+    clean_pieces: usize,
+    // Whether each of the last RECENT_PIECES_WINDOW pieces was clean, oldest first
+    recent_pieces: VecDeque<bool>,
+    rng: StdRng,
+}
+
+impl FinesseTrainer {
+    /// Creates a trainer with no pieces judged.
+    pub fn new() -> FinesseTrainer {
+        return FinesseTrainer::with_results(0, 0, &[]);
+    }
+
+    /// Creates a trainer carrying on from earlier sessions, which judged `pieces` pieces and
+    /// found `clean_pieces` of them clean. `recent_pieces` says whether each of the latest was
+    /// clean, oldest first; only the last [`RECENT_PIECES_WINDOW`] are kept.
+    pub fn with_results(pieces: usize, clean_pieces: usize, recent_pieces: &[bool]) -> FinesseTrainer {
+        let window_start = recent_pieces.len().saturating_sub(RECENT_PIECES_WINDOW);
+
+        return FinesseTrainer {
+            target: None,
+            last_verdict: None,
+            pieces,
+            clean_pieces: clean_pieces.min(pieces),
+            recent_pieces: recent_pieces[window_start..].iter().copied().collect(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Picks a new random target for the game's active piece.
+    pub fn pick_target(&mut self, tetris: &Tetris) {
+        let placements = finesse_placements(tetris.board(), tetris.active_piece(), tetris.rotation_system());
+        self.target = placements.choose(&mut self.rng).cloned();
+    }
+
+    /// Judges the piece that just locked in `tetris` against the target.
+    pub fn judge_piece(&mut self, tetris: &Tetris, finesse: Option<FinesseResult>) {
+        let target = match self.target.take() {
+            Some(target) => target,
+            None => return,
+        };
+
+        let hit_target = tetris.last_locked_piece().is_some_and(|locked_piece| {
+            let locked_placement = Placement { piece: *locked_piece, inputs: Vec::new() };
+            return locked_placement.covers_same_cells(&target);
+        });
+
+        let verdict = TrainerVerdict { hit_target, finesse, target_inputs: target.inputs };
+        self.pieces += 1;
+        if verdict.is_clean() {
+            self.clean_pieces += 1;
+        }
+        self.recent_pieces.push_back(verdict.is_clean());
+        if self.recent_pieces.len() > RECENT_PIECES_WINDOW {
+            self.recent_pieces.pop_front();
+        }
+        self.last_verdict = Some(verdict);
+    }
+
+    /// Draws the target onto `board`, over empty cells and the ghost piece.
+    pub fn draw_target(&self, board: &mut Board) {
+        if let Some(target) = &self.target {
+            for cell in target.piece.cells() {
+                for (row, column) in board.blocks_under(cell).collect::<Vec<(usize, usize)>>() {
+                    let block = &mut board.blocks[row][column];
+                    if block.block_type == BlockType::Empty || block.block_type == BlockType::Ghost {
+                        block.block_type = BlockType::Target;
+                    }
+                }
+            }
+        }
+    }
+
+    /// How many pieces have been judged, including in earlier sessions.
+    pub fn pieces(&self) -> usize {
+        return self.pieces;
+    }
+
+    /// How many pieces hit their target with perfect finesse, including in earlier sessions.
+    pub fn clean_pieces(&self) -> usize {
+        return self.clean_pieces;
+    }
+
+    /// Whether each of the last [`RECENT_PIECES_WINDOW`] pieces was clean, oldest first.
+    pub fn recent_pieces(&self) -> Vec<bool> {
+        return self.recent_pieces.iter().copied().collect();
+    }
+
+    /// The share of clean pieces over every piece judged, from 0 to 1.
+    pub fn overall_accuracy(&self) -> Option<f64> {
+        return accuracy(self.pieces, self.clean_pieces);
+    }
+
+    /// The share of clean pieces among the last [`RECENT_PIECES_WINDOW`] pieces, from 0 to 1.
+    /// Compare with [`FinesseTrainer::overall_accuracy`] to see whether the player is improving.
+    pub fn recent_accuracy(&self) -> Option<f64> {
+        let clean_pieces = self.recent_pieces.iter().filter(|is_clean| **is_clean).count();
+        return accuracy(self.recent_pieces.len(), clean_pieces);
+    }
+}
+
+impl Default for FinesseTrainer {
+    fn default() -> FinesseTrainer {
+        return FinesseTrainer::new();
+    }
+}
+
+fn accuracy(pieces: usize, clean_pieces: usize) -> Option<f64> {
+    if pieces == 0 {
+        return None;
+    }
+
+    return Some(clean_pieces as f64 / pieces as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Vector2;
+    use crate::randomizer::Randomizer;
+    use crate::tetris::{drop_piece, slide_piece};
+
+    // Plays `inputs` on the active piece, telling the tracker about every press finesse counts
+    fn play(tetris: &mut Tetris, tracker: &mut FinesseTracker, inputs: &[Input]) {
+        for input in inputs {
+            if finesse_cost(&[*input]) > 0 {
+                tracker.record_key_press();
+            }
+            tetris.apply_input(*input);
+        }
+    }
+
+    // The inputs finesse expects for dropping the active piece against the left wall, unrotated
+    fn inputs_to_left_wall(tetris: &Tetris) -> Vec<Input> {
+        let board = tetris.board();
+        let target = drop_piece(&slide_piece(tetris.active_piece(), board, Vector2 { x: -1, y: 0 }), board);
+        return minimal_inputs(board, tetris.active_piece(), &target, tetris.rotation_system()).unwrap();
+    }
+
+    #[test]
+    fn drops_and_hold_are_free() {
+        assert_eq!(finesse_cost(&[Input::Hold, Input::DasLeft, Input::RotateClockwise, Input::SoftDrop, Input::SonicDrop, Input::HardDrop]), 2);
+    }
+
+    #[test]
+    fn placing_with_the_fewest_presses_is_clean() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 3);
+        let mut tracker = FinesseTracker::new();
+
+        tracker.start_piece(&tetris);
+        let inputs = inputs_to_left_wall(&tetris);
+        assert_eq!(inputs, Vec::from([Input::DasLeft, Input::HardDrop]));
+        play(&mut tetris, &mut tracker, &inputs);
+
+        let result = tracker.finish_piece(&tetris).unwrap();
+        assert_eq!(result.key_presses, 1);
+        assert_eq!(result.faults, 0);
+        assert_eq!((tracker.pieces, tracker.faults), (1, 0));
+    }
+
+    #[test]
+    fn extra_presses_are_faults() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 3);
+        let mut tracker = FinesseTracker::new();
+
+        tracker.start_piece(&tetris);
+        let inputs = inputs_to_left_wall(&tetris);
+        play(&mut tetris, &mut tracker, &[Input::Right, Input::RotateClockwise, Input::RotateCounterclockwise]);
+        play(&mut tetris, &mut tracker, &inputs);
+
+        let result = tracker.finish_piece(&tetris).unwrap();
+        assert_eq!(result.expected_inputs, inputs);
+        assert_eq!(result.key_presses, 4);
+        assert_eq!(result.faults, 3);
+        assert_eq!((tracker.pieces, tracker.faults), (1, 3));
+    }
+
+    #[test]
+    fn holding_starts_the_count_again_for_the_new_piece() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 3);
+        let mut tracker = FinesseTracker::new();
+
+        tracker.start_piece(&tetris);
+        play(&mut tetris, &mut tracker, &[Input::Left, Input::Hold]);
+        // The game starts the next piece's count whenever a hold brings one in
+        tracker.start_piece(&tetris);
+
+        let inputs = inputs_to_left_wall(&tetris);
+        play(&mut tetris, &mut tracker, &inputs);
+
+        let result = tracker.finish_piece(&tetris).unwrap();
+        assert_eq!(result.key_presses, 1);
+        assert_eq!(result.faults, 0);
+    }
+
+    #[test]
+    fn the_trainer_only_counts_clean_hits_on_the_target() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 3);
+        let mut tracker = FinesseTracker::new();
+        let mut trainer = FinesseTrainer::new();
+
+        tracker.start_piece(&tetris);
+        trainer.pick_target(&tetris);
+        let target_inputs = trainer.target.as_ref().unwrap().inputs.clone();
+        play(&mut tetris, &mut tracker, &target_inputs);
+        trainer.judge_piece(&tetris, tracker.finish_piece(&tetris));
+        assert!(trainer.last_verdict.as_ref().unwrap().is_clean());
+
+        // The trainer clears the board between pieces
+        tetris.clear_board();
+        tracker.start_piece(&tetris);
+        trainer.pick_target(&tetris);
+        let target_inputs = trainer.target.as_ref().unwrap().inputs.clone();
+        play(&mut tetris, &mut tracker, &[Input::Left, Input::Right]);
+        play(&mut tetris, &mut tracker, &target_inputs);
+        trainer.judge_piece(&tetris, tracker.finish_piece(&tetris));
+        let verdict = trainer.last_verdict.as_ref().unwrap();
+        assert!(verdict.hit_target);
+        assert!(!verdict.is_clean());
+
+        assert_eq!((trainer.pieces(), trainer.clean_pieces()), (2, 1));
+        assert_eq!(trainer.overall_accuracy(), Some(0.5));
+        assert_eq!(trainer.recent_pieces(), Vec::from([true, false]));
+    }
+
+    #[test]
+    fn trainer_results_carry_over_with_only_the_recent_window_kept() {
+        let recent_pieces: Vec<bool> = (0..30).map(|index| index >= 10).collect();
+        let trainer = FinesseTrainer::with_results(100, 40, &recent_pieces);
+
+        assert_eq!(trainer.recent_pieces().len(), RECENT_PIECES_WINDOW);
+        assert_eq!(trainer.recent_accuracy(), Some(1.0));
+        assert_eq!(trainer.overall_accuracy(), Some(0.4));
+        assert_eq!(FinesseTrainer::new().overall_accuracy(), None);
+    }
+}
@@ -1,21 +1,29 @@
+/// A single step of the speed curve.
 #[derive(Clone)]
 #[derive(Copy)]
-struct Level {
+pub struct Level {
+    /// How many cleared lines it takes to advance past this level.
     pub lines_to_next_level: u8,
+    /// Gravity interval in microseconds.
     pub tick_interval: u128,
 }
 
 impl Level {
+    /// Creates a level from the classic "frames per row at 60 FPS" gravity notation.
     pub fn new(lines_to_next_level: u8, frames_per_block_at_60: u16) -> Level {
-        return Level { lines_to_next_level, tick_interval: 16667 as u128 * frames_per_block_at_60 as u128 }
+        return Level { lines_to_next_level, tick_interval: 16667_u128 * frames_per_block_at_60 as u128 }
     }
 }
 
+/// Whether adding cleared lines moved the player to the next level.
 pub enum AddClearedLinesResult {
+    /// The player advanced to the contained level.
     LevelIncreased(Level),
+    /// The player is still on the same level.
     LevelStayedTheSame,
 }
 
+/// Tracks the current level along a speed curve.
 pub struct Levels {
     current_level_index: usize,
     accumulated_lines: u8,
@@ -23,6 +31,7 @@ pub struct Levels {
 }
 
 impl Levels {
+    /// The classic NES speed curve.
     pub fn classic() -> Levels {
         return Levels {
             current_level_index: 0,
@@ -62,8 +71,8 @@ impl Levels {
         };
     }
 
+    /// Counts cleared lines towards the next level.
     pub fn add_cleared_lines(&mut self, cleared_lines: u8) -> AddClearedLinesResult {
-        let mut level_increased = false;
         self.accumulated_lines += cleared_lines;
         let required_lines_to_next_level = self.levels[self.current_level_index].lines_to_next_level;
         if self.accumulated_lines >= required_lines_to_next_level && self.current_level_index < self.levels.len() {
@@ -0,0 +1,28 @@
+//! The rules engine behind Terminal Tetris.
+//!
+//! This crate knows nothing about terminals, keyboards or wall-clock time. It holds the board,
+//! the pieces and the piece sequence, and exposes the actions a player (or a bot) can take:
+//!
+//! ```
+//! use tetris_core::tetris::{MoveOutcome, Tetris};
+//! use tetris_core::randomizer::Randomizer;
+//!
+//! let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 42);
+//! tetris.try_and_move_left();
+//! tetris.try_and_rotate_clockwise();
+//! let outcome = tetris.slam();
+//! assert_eq!(outcome, MoveOutcome::SpawnedNewPieceAndClearedLines(0));
+//! ```
+//!
+//! Gravity is driven from outside, by calling [`tetris::Tetris::move_down_and_stick`] whenever a
+//! [`ticker::Ticker`] reports a tick.
+
+pub mod tetris;
+pub mod piece;
+pub mod randomizer;
+pub mod levels;
+pub mod ticker;
+pub mod movegen;
+pub mod bot;
+pub mod finesse;
+pub mod attack;
@@ -0,0 +1,207 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::piece::{Piece, Vector2};
+use crate::tetris::{Board, drop_piece, Input, Rotation, RotationSystem, slide_piece, try_and_move_piece, try_and_rotate_piece};
+
+/// A final resting place for a piece, and the inputs that get it there.
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Placement {
+    /// The piece as it will be locked.
+    pub piece: Piece,
+    /// The inputs that take the piece from where it is now to its final position, ending with [`Input::HardDrop`].
+    /// Starts with [`Input::Hold`] if the placement is for the piece that holding would bring in.
+    pub inputs: Vec<Input>,
+}
+
+impl Placement {
+    /// Whether the placement is for the piece that holding would bring in.
+    pub fn uses_hold(&self) -> bool {
+        return self.inputs.first() == Some(&Input::Hold);
+    }
+
+    /// Whether both placements lock into exactly the same cells.
+    pub fn covers_same_cells(&self, other: &Placement) -> bool {
+        return sorted_cells(&self.piece) == sorted_cells(&other.piece);
+    }
+}
+
+/// Every input that moves the piece without locking it, in the order ties are broken in:
+/// rotations first, then taps, then auto-repeat slides, then drops.
+const MOVEMENT_INPUTS: [Input; 8] = [
+    Input::RotateClockwise,
+    Input::RotateCounterclockwise,
+    Input::Left,
+    Input::Right,
+    Input::DasLeft,
+    Input::DasRight,
+    Input::SonicDrop,
+    Input::SoftDrop,
+];
+
+/// Finds every distinct placement `piece` can reach on `board`, including slides and tucks under
+/// overhangs (via soft drop) and spins through whatever kicks `rotation_system` allows.
+///
+/// Placements covering the same cells are reported once, with the shortest sequence of inputs.
+/// Every input counts as one key press, so holding a direction until the wall ([`Input::DasLeft`],
+/// [`Input::DasRight`]) or holding soft drop until the floor ([`Input::SonicDrop`]) costs the same as a tap.
+pub fn find_placements(board: &Board, piece: &Piece, rotation_system: RotationSystem) -> Vec<Placement> {
+    return find_placements_by_cost(board, piece, rotation_system, |_| 1);
+}
+
+/// Like [`find_placements`], but each placement comes with the sequence of inputs that costs the
+/// least by `cost`, and of those the shortest. Inputs can be free, such as drops when judging finesse.
+pub fn find_placements_by_cost(board: &Board, piece: &Piece, rotation_system: RotationSystem, cost: impl Fn(Input) -> u32) -> Vec<Placement> {
+    // Every piece we've reached, with the index of the piece it was reached from and the input used.
+    let mut visited: Vec<(Piece, Option<(usize, Input)>)> = Vec::from([(*piece, None)]);
+    // The cheapest way found to each state so far, as its cost and then its number of inputs
+    let mut best_costs: HashMap<(i8, i8, usize), (u32, u32)> = HashMap::from([(state_key(piece), (0, 0))]);
+    let mut finished_states: HashSet<(i8, i8, usize)> = HashSet::new();
+    let mut seen_placements: HashSet<[Vector2; 4]> = HashSet::new();
+    // Cheapest first, with ties going to whichever was reached first
+    let mut queue: BinaryHeap<Reverse<(u32, u32, usize)>> = BinaryHeap::from([Reverse((0, 0, 0))]);
+    let mut placements: Vec<Placement> = Vec::new();
+
+    while let Some(Reverse((current_cost, current_length, index))) = queue.pop() {
+        let current_piece = visited[index].0;
+        if !finished_states.insert(state_key(&current_piece)) {
+            continue;
+        }
+
+        let dropped_piece = drop_piece(&current_piece, board);
+        if seen_placements.insert(sorted_cells(&dropped_piece)) {
+            let mut inputs = path_to(&visited, index);
+            inputs.push(Input::HardDrop);
+            placements.push(Placement { piece: dropped_piece, inputs });
+        }
+
+        for input in MOVEMENT_INPUTS {
+            let next_piece = match input {
+                Input::Left                   => try_and_move_piece(&current_piece, board, Vector2 { x: -1, y: 0 }),
+                Input::Right                  => try_and_move_piece(&current_piece, board, Vector2 { x: 1, y: 0 }),
+                Input::DasLeft                => Some(slide_piece(&current_piece, board, Vector2 { x: -1, y: 0 })),
+                Input::DasRight               => Some(slide_piece(&current_piece, board, Vector2 { x: 1, y: 0 })),
+                Input::SoftDrop               => try_and_move_piece(&current_piece, board, Vector2 { x: 0, y: 1 }),
+                Input::SonicDrop              => Some(slide_piece(&current_piece, board, Vector2 { x: 0, y: 1 })),
+                Input::RotateClockwise        => try_and_rotate_piece(&current_piece, board, Rotation::Clockwise, rotation_system),
+                Input::RotateCounterclockwise => try_and_rotate_piece(&current_piece, board, Rotation::Counterclockwise, rotation_system),
+                _ => None,
+            };
+
+            if let Some(next_piece) = next_piece {
+                let next_cost = (current_cost + cost(input), current_length + 1);
+                let next_key = state_key(&next_piece);
+                if best_costs.get(&next_key).is_none_or(|best_cost| next_cost < *best_cost) {
+                    best_costs.insert(next_key, next_cost);
+                    visited.push((next_piece, Some((index, input))));
+                    queue.push(Reverse((next_cost.0, next_cost.1, visited.len() - 1)));
+                }
+            }
+        }
+    }
+
+    return placements;
+}
+
+fn state_key(piece: &Piece) -> (i8, i8, usize) {
+    return (piece.position.x, piece.position.y, piece.rotation());
+}
+
+fn sorted_cells(piece: &Piece) -> [Vector2; 4] {
+    let mut cells = piece.cells();
+    cells.sort_by_key(|cell| (cell.y, cell.x));
+    return cells;
+}
+
+fn path_to(visited: &[(Piece, Option<(usize, Input)>)], index: usize) -> Vec<Input> {
+    let mut inputs: Vec<Input> = Vec::new();
+    let mut current_index = index;
+
+    while let Some((parent_index, input)) = visited[current_index].1 {
+        inputs.push(input);
+        current_index = parent_index;
+    }
+
+    inputs.reverse();
+    return inputs;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::{BlockType, PieceType};
+    use crate::tetris::{Block, Tetris};
+
+    const FILLER: Block = Block { block_type: BlockType::J, locked_at: 0 };
+
+    fn spawned_piece(board: &Board, piece_type: PieceType) -> Piece {
+        let mut piece = Piece::from_piece_type(&piece_type);
+        Tetris::move_piece_to_spawn_point(&mut piece, board);
+        return piece;
+    }
+
+    // Plays the inputs the way the game would, up to the hard drop
+    fn follow_inputs(board: &Board, piece: &Piece, inputs: &[Input]) -> Piece {
+        let mut current_piece = *piece;
+
+        for input in inputs {
+            current_piece = match input {
+                Input::Left                   => try_and_move_piece(&current_piece, board, Vector2 { x: -1, y: 0 }).unwrap(),
+                Input::Right                  => try_and_move_piece(&current_piece, board, Vector2 { x: 1, y: 0 }).unwrap(),
+                Input::DasLeft                => slide_piece(&current_piece, board, Vector2 { x: -1, y: 0 }),
+                Input::DasRight               => slide_piece(&current_piece, board, Vector2 { x: 1, y: 0 }),
+                Input::SoftDrop               => try_and_move_piece(&current_piece, board, Vector2 { x: 0, y: 1 }).unwrap(),
+                Input::SonicDrop              => slide_piece(&current_piece, board, Vector2 { x: 0, y: 1 }),
+                Input::RotateClockwise        => try_and_rotate_piece(&current_piece, board, Rotation::Clockwise, RotationSystem::Kicks).unwrap(),
+                Input::RotateCounterclockwise => try_and_rotate_piece(&current_piece, board, Rotation::Counterclockwise, RotationSystem::Kicks).unwrap(),
+                Input::HardDrop               => drop_piece(&current_piece, board),
+                Input::Hold                   => panic!("placements for the active piece never hold"),
+            };
+        }
+
+        return current_piece;
+    }
+
+    #[test]
+    fn an_empty_board_has_every_column_and_rotation() {
+        let board = Board::new(10, 20, 1);
+
+        for (piece_type, expected_placements) in [(PieceType::O, 9), (PieceType::I, 17), (PieceType::S, 17), (PieceType::T, 34), (PieceType::L, 34)] {
+            let placements = find_placements(&board, &spawned_piece(&board, piece_type), RotationSystem::Kicks);
+            assert_eq!(placements.len(), expected_placements, "{:?}", piece_type);
+        }
+    }
+
+    #[test]
+    fn every_placement_is_reached_by_its_inputs() {
+        let mut board = Board::new(10, 20, 1);
+        // An overhang over the left of the floor, only reachable by sliding under it
+        for column in 0..4 {
+            board.blocks[37][column] = FILLER;
+        }
+        let piece = spawned_piece(&board, PieceType::T);
+
+        let placements = find_placements(&board, &piece, RotationSystem::Kicks);
+        for placement in &placements {
+            assert_eq!(placement.inputs.last(), Some(&Input::HardDrop));
+            assert_eq!(sorted_cells(&follow_inputs(&board, &piece, &placement.inputs)), sorted_cells(&placement.piece));
+        }
+
+        let tucks: Vec<&Placement> = placements.iter().filter(|placement| placement.piece.cells().iter().all(|cell| cell.y > 37 && cell.x < 4)).collect();
+        assert!(!tucks.is_empty());
+        assert!(tucks.iter().all(|tuck| tuck.inputs.contains(&Input::SonicDrop) || tuck.inputs.contains(&Input::SoftDrop)));
+    }
+
+    #[test]
+    fn placements_take_the_cheapest_inputs() {
+        let board = Board::new(10, 20, 1);
+        let piece = spawned_piece(&board, PieceType::O);
+        let leftmost = |placements: Vec<Placement>| placements.into_iter().find(|placement| placement.piece.cells().iter().any(|cell| cell.x == 0)).unwrap();
+
+        assert_eq!(leftmost(find_placements(&board, &piece, RotationSystem::Kicks)).inputs, Vec::from([Input::DasLeft, Input::HardDrop]));
+
+        let expensive_slides = |input: Input| if matches!(input, Input::DasLeft | Input::DasRight) { 10 } else { 1 };
+        let placement = leftmost(find_placements_by_cost(&board, &piece, RotationSystem::Kicks, expensive_slides));
+        assert_eq!(placement.inputs, Vec::from([Input::Left, Input::Left, Input::Left, Input::Left, Input::HardDrop]));
+    }
+}
@@ -0,0 +1,366 @@
+/// The seven tetrominoes.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum PieceType {
+    O,
+    I,
+    L,
+    J,
+    S,
+    Z,
+    T
+}
+
+impl PieceType {
+    /// Maps a piece-coloured block back to its piece. Panics for anything that isn't a piece block.
+    pub fn from_block_type(block_type: &BlockType) -> PieceType {
+        match block_type {
+            BlockType::O => PieceType::O,
+            BlockType::I => PieceType::I,
+            BlockType::L => PieceType::L,
+            BlockType::J => PieceType::J,
+            BlockType::S => PieceType::S,
+            BlockType::Z => PieceType::Z,
+            BlockType::T => PieceType::T,
+
+            _ => {panic!("Tried to make a piece_type from an invalid block_type")}
+        }
+    }
+}
+
+/// What occupies a cell: a block of one of the pieces, garbage from an opponent, the ghost piece, the
+/// outline of a hidden block, a target or hint marker, or nothing.
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum BlockType {
+    O,
+    I,
+    L,
+    J,
+    S,
+    Z,
+    T,
+    Garbage,
+    Ghost,
+    Outline,
+    Target,
+    Hint,
+    AlternativeHint,
+    Empty,
+}
+
+/// A position on a board, or an offset from a piece's origin. `y` grows downwards.
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(Eq)]
+#[derive(Hash)]
+#[derive(Debug)]
+pub struct Vector2 {
+    /// Column.
+    pub x: i8,
+    /// Row.
+    pub y: i8,
+}
+
+/// A tetromino with its position and all of its rotation states.
+#[derive(Copy)]
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct Piece {
+    /// Where the piece's origin is on the board.
+    pub position: Vector2,
+    /// The kind of block the piece is made of.
+    pub block_type: BlockType,
+    current_rotation: usize,
+    rotations: [[Vector2; 4]; 4],
+}
+
+/// Piece rotations are in clockwise order
+impl Piece {
+    /// Offsets of the piece's four blocks from its position, in the current rotation.
+    pub fn blocks(self: &Piece) -> &[Vector2; 4] {
+        return &self.rotations[self.current_rotation];
+    }
+
+    /// Index of the current rotation state. 0 is the spawn rotation, counting clockwise.
+    pub fn rotation(self: &Piece) -> usize {
+        return self.current_rotation;
+    }
+
+    /// Board positions of the piece's four blocks.
+    pub fn cells(self: &Piece) -> [Vector2; 4] {
+        return self.blocks().map(|block| Vector2 { x: self.position.x + block.x, y: self.position.y + block.y });
+    }
+
+    pub(crate) fn rotate_clockwise(self: &mut Piece) {
+        if self.current_rotation == 3 {
+            self.current_rotation = 0;
+        } else {
+            self.current_rotation += 1;
+        }
+    }
+
+    pub(crate) fn rotate_counterclockwise(self: &mut Piece) {
+        if self.current_rotation == 0 {
+            self.current_rotation = 3;
+        } else {
+            self.current_rotation -= 1;
+        }
+    }
+
+    /// Creates a piece of the given type at the origin, in its spawn rotation.
+    pub fn from_piece_type(piece_type: &PieceType) -> Piece {
+        return match piece_type {
+            PieceType::O => Piece::make_o(),
+            PieceType::I => Piece::make_i(),
+            PieceType::L => Piece::make_l(),
+            PieceType::J => Piece::make_j(),
+            PieceType::S => Piece::make_s(),
+            PieceType::Z => Piece::make_z(),
+            PieceType::T => Piece::make_t(),
+        }
+    }
+
+    fn make_o() -> Piece {
+        return Piece {
+            block_type: BlockType::O,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x: 0, y: 0 },
+                    Vector2 { x: 1, y: 0 },
+                    Vector2 { x: 0, y: 1 },
+                    Vector2 { x: 1, y: 1 },
+                ],
+                [
+                    Vector2 { x: 0, y: 0 },
+                    Vector2 { x: 1, y: 0 },
+                    Vector2 { x: 0, y: 1 },
+                    Vector2 { x: 1, y: 1 },
+                ],
+                [
+                    Vector2 { x: 0, y: 0 },
+                    Vector2 { x: 1, y: 0 },
+                    Vector2 { x: 0, y: 1 },
+                    Vector2 { x: 1, y: 1 },
+                ],
+                [
+                    Vector2 { x: 0, y: 0 },
+                    Vector2 { x: 1, y: 0 },
+                    Vector2 { x: 0, y: 1 },
+                    Vector2 { x: 1, y: 1 },
+                ],
+            ],
+        }
+    }
+
+    fn make_l() -> Piece {
+        return Piece {
+            block_type: BlockType::L,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x: -1, y:  0 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x:  1, y:  0 },
+                    Vector2 { x:  1, y: -1 },
+                ],
+                [
+                    Vector2 { x: 0, y: -1 },
+                    Vector2 { x: 0, y:  0 },
+                    Vector2 { x: 0, y:  1 },
+                    Vector2 { x: 1, y:  1 },
+                ],
+                [
+                    Vector2 { x: -1, y: 1 },
+                    Vector2 { x: -1, y: 0 },
+                    Vector2 { x: 0,  y: 0 },
+                    Vector2 { x: 1,  y: 0 },
+                ],
+                [
+                    Vector2 { x: -1, y: -1 },
+                    Vector2 { x:  0, y: -1 },
+                    Vector2 { x:  0, y: 0  },
+                    Vector2 { x:  0, y: 1  },
+                ],
+            ],
+        }
+    }
+
+    fn make_j() -> Piece {
+        return Piece {
+            block_type: BlockType::J,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x: -1, y: -1 },
+                    Vector2 { x: -1, y:  0 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x:  1, y:  0 },
+                ],
+                [
+                    Vector2 { x: 1, y: -1 },
+                    Vector2 { x: 0, y: -1 },
+                    Vector2 { x: 0, y:  0 },
+                    Vector2 { x: 0, y:  1 },
+                ],
+                [
+                    Vector2 { x: -1, y: 0 },
+                    Vector2 { x:  0, y: 0 },
+                    Vector2 { x:  1, y: 0 },
+                    Vector2 { x:  1, y: 1 },
+                ],
+                [
+                    Vector2 { x:  0, y: -1 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x:  0, y:  1 },
+                    Vector2 { x: -1, y:  1 },
+                ],
+            ],
+        }
+    }
+
+    fn make_s() -> Piece {
+        return Piece {
+            block_type: BlockType::S,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x:  1, y: 0 },
+                    Vector2 { x:  0, y: 0 },
+                    Vector2 { x:  0, y: 1 },
+                    Vector2 { x: -1, y: 1 },
+                ],
+                [
+                    Vector2 { x: 0, y: -1 },
+                    Vector2 { x: 0, y:  0 },
+                    Vector2 { x: 1, y:  0 },
+                    Vector2 { x: 1, y:  1 },
+                ],
+                [
+                    Vector2 { x:  1, y: 1 },
+                    Vector2 { x:  0, y: 1 },
+                    Vector2 { x:  0, y: 2 },
+                    Vector2 { x: -1, y: 2 },
+                ],
+                [
+                    Vector2 { x: -1, y: -1 },
+                    Vector2 { x: -1, y:  0 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x:  0, y:  1 },
+                ],
+            ],
+        }
+    }
+
+    fn make_z() -> Piece {
+        return Piece {
+            block_type: BlockType::Z,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x: -1, y: 0 },
+                    Vector2 { x:  0, y: 0 },
+                    Vector2 { x:  0, y: 1 },
+                    Vector2 { x:  1, y: 1 },
+                ],
+                [
+                    Vector2 { x: 1, y: -1 },
+                    Vector2 { x: 1, y:  0 },
+                    Vector2 { x: 0, y:  0 },
+                    Vector2 { x: 0, y:  1 },
+                ],
+                [
+                    Vector2 { x: -1, y: 1 },
+                    Vector2 { x:  0, y: 1 },
+                    Vector2 { x:  0, y: 2 },
+                    Vector2 { x:  1, y: 2 },
+                ],
+                [
+                    Vector2 { x:  0, y: -1 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x: -1, y:  0 },
+                    Vector2 { x: -1, y:  1 },
+                ],
+            ],
+        }
+    }
+
+    fn make_i() -> Piece {
+        return Piece {
+            block_type: BlockType::I,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x: -1, y: 0 },
+                    Vector2 { x:  0, y: 0 },
+                    Vector2 { x:  1, y: 0 },
+                    Vector2 { x:  2, y: 0 },
+                ],
+                [
+                    Vector2 { x: 1, y: -1 },
+                    Vector2 { x: 1, y:  0 },
+                    Vector2 { x: 1, y:  1 },
+                    Vector2 { x: 1, y:  2 },
+                ],
+                [
+                    Vector2 { x: -1, y: 1 },
+                    Vector2 { x:  0, y: 1 },
+                    Vector2 { x:  1, y: 1 },
+                    Vector2 { x:  2, y: 1 },
+                ],
+                [
+                    Vector2 { x: 0, y: -1 },
+                    Vector2 { x: 0, y:  0 },
+                    Vector2 { x: 0, y:  1 },
+                    Vector2 { x: 0, y:  2 },
+                ],
+            ],
+        }
+    }
+
+    fn make_t() -> Piece {
+        return Piece {
+            block_type: BlockType::T,
+            position: Vector2 { x: 0, y: 0 },
+            current_rotation: 0,
+            rotations: [
+                [
+                    Vector2 { x: -1, y:  0 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x:  0, y: -1 },
+                    Vector2 { x:  1, y:  0 },
+                ],
+                [
+                    Vector2 { x: 0, y: -1 },
+                    Vector2 { x: 0, y:  0 },
+                    Vector2 { x: 1, y:  0 },
+                    Vector2 { x: 0, y:  1 },
+                ],
+                [
+                    Vector2 { x: -1, y: 0 },
+                    Vector2 { x:  0, y: 0 },
+                    Vector2 { x:  0, y: 1 },
+                    Vector2 { x:  1, y: 0 },
+                ],
+                [
+                    Vector2 { x:  0, y: -1 },
+                    Vector2 { x:  0, y:  0 },
+                    Vector2 { x: -1, y:  0 },
+                    Vector2 { x:  0, y:  1 },
+                ],
+            ],
+        }
+    }
+}
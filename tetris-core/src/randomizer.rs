@@ -0,0 +1,64 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::piece::PieceType;
+
+/// How many pieces are pre-generated for a game. The sequence wraps around once exhausted.
+pub const SEQUENCE_LENGTH: usize = 350;
+
+/// The pre-generated order in which pieces are handed out.
+pub type PieceSequence = [PieceType; SEQUENCE_LENGTH];
+
+const ALL_PIECE_TYPES: [PieceType; 7] = [
+    PieceType::O,
+    PieceType::I,
+    PieceType::L,
+    PieceType::J,
+    PieceType::S,
+    PieceType::Z,
+    PieceType::T,
+];
+
+/// The strategy used to generate the piece sequence.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Randomizer {
+    /// Guideline 7-bag: every run of 7 pieces contains each piece exactly once.
+    SevenBag,
+    /// Every piece is picked independently, so droughts and floods are possible.
+    Memoryless,
+}
+
+impl Randomizer {
+    /// Generates a full piece sequence using the given random number generator.
+    pub fn make_sequence<R: Rng>(&self, rng: &mut R) -> PieceSequence {
+        return match self {
+            Randomizer::SevenBag => make_seven_bag_sequence(rng),
+            Randomizer::Memoryless => make_memoryless_sequence(rng),
+        }
+    }
+}
+
+fn make_seven_bag_sequence<R: Rng>(rng: &mut R) -> PieceSequence {
+    let mut sequence: PieceSequence = [PieceType::I; SEQUENCE_LENGTH];
+
+    for bag_index in 0..SEQUENCE_LENGTH / 7 {
+        let mut bag = ALL_PIECE_TYPES;
+        bag.shuffle(rng);
+
+        sequence[bag_index * 7..(bag_index + 1) * 7].copy_from_slice(&bag);
+    }
+
+    return sequence;
+}
+
+fn make_memoryless_sequence<R: Rng>(rng: &mut R) -> PieceSequence {
+    let mut sequence: PieceSequence = [PieceType::I; SEQUENCE_LENGTH];
+
+    for piece_type in sequence.iter_mut() {
+        *piece_type = *ALL_PIECE_TYPES.choose(rng).unwrap();
+    }
+
+    return sequence;
+}
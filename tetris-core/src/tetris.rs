@@ -0,0 +1,1526 @@
+use std::collections::VecDeque;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::attack::AttackTable;
+use crate::movegen::{find_placements, Placement};
+use crate::piece::{BlockType, Piece, PieceType, Vector2};
+use crate::randomizer::{PieceSequence, Randomizer};
+use crate::tetris::MoveOutcome::{NothingSpecial, SpawnedNewPieceAndClearedLines};
+
+/// Width of the playfield unless [`Tetris::with_board_size`] says otherwise, in blocks.
+pub const DEFAULT_BOARD_WIDTH: usize = 10;
+/// Height of the part of the playfield players can see unless [`Tetris::with_board_size`] says otherwise, in blocks.
+pub const DEFAULT_VISIBLE_BOARD_HEIGHT: usize = 20;
+/// The narrowest playfield allowed, just wide enough for an I piece lying flat. Big mode needs twice that.
+pub const MIN_BOARD_WIDTH: usize = 4;
+/// The widest playfield allowed.
+pub const MAX_BOARD_WIDTH: usize = 20;
+/// The fewest visible rows allowed.
+pub const MIN_VISIBLE_BOARD_HEIGHT: usize = 10;
+/// The most visible rows allowed.
+pub const MAX_VISIBLE_BOARD_HEIGHT: usize = 40;
+/// The most upcoming pieces [`Tetris::set_preview_length`] allows.
+pub const MAX_PREVIEW_LENGTH: usize = 7;
+/// How many upcoming pieces are shown unless [`Tetris::set_preview_length`] says otherwise.
+pub const DEFAULT_PREVIEW_LENGTH: usize = 4;
+
+// How long a line clear shows the whole stack for, when stack flashing is on, in microseconds
+const STACK_FLASH_TIME: u128 = 500000;
+
+// Preview boards are wide enough for an I piece with a block either side
+const PREVIEW_WIDTH: usize = 6;
+// Each previewed piece gets two rows plus a gap row under it, after a gap row at the very top
+const PREVIEW_SLOT_HEIGHT: usize = 3;
+
+/// A render-ready picture of the game, with the active and ghost pieces already drawn onto the board.
+pub struct TetrisState {
+    /// The playfield, including the active piece and its ghost.
+    pub board: Board,
+    /// The upcoming pieces, laid out one under the other.
+    pub next_pieces_board: NextPiecesBoard,
+    /// The held piece, if any.
+    pub held_piece_board: HeldPieceBoard,
+    /// Suggested placements for the active piece, best first, for frontends to draw as an overlay.
+    /// The engine leaves this empty.
+    pub hints: Vec<Piece>,
+    /// How many pieces have locked so far, so frontends can tell when a new one has.
+    pub pieces_locked: u32,
+    /// The most recently locked piece, exactly where it locked.
+    pub last_locked_piece: Option<Piece>,
+    /// Full rows still on the board while the line clear delay runs. See [`Tetris::set_line_clear_delay`].
+    pub clearing_rows: Vec<usize>,
+    /// The running combo. See [`Tetris::combo`].
+    pub combo: Option<u32>,
+    /// The running back-to-back chain. See [`Tetris::back_to_back`].
+    pub back_to_back: Option<u32>,
+    /// Garbage lines waiting to come up the board. See [`Tetris::pending_garbage`].
+    pub pending_garbage: u32,
+}
+
+/// What happened as a result of an action on [`Tetris`].
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum MoveOutcome {
+    /// The active piece locked, the given number of lines were cleared and the next piece spawned.
+    SpawnedNewPieceAndClearedLines(u8),
+    /// The active piece locked and filled the given number of lines, which stay on the board
+    /// until [`Tetris::finish_line_clear`] is called.
+    ClearingLines(u8),
+    /// The next piece could not be spawned, or the last one locked entirely inside the hidden buffer.
+    GameOver,
+    /// Nothing worth reacting to.
+    NothingSpecial,
+    /// The active piece touched the stack or the floor.
+    MadeContactOnBottom,
+}
+
+/// Whether a lock counts as a T-spin, by the three-corner rule.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum TSpin {
+    /// Not a T-spin.
+    None,
+    /// A T-spin where only one of the two corners the T points at is filled.
+    Mini,
+    /// A T-spin where both corners the T points at are filled.
+    Full,
+}
+
+/// How much of the locked stack [`Tetris::state`] shows. The rules always play on the real board.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub enum StackVisibility {
+    /// Every locked block is shown.
+    Visible,
+    /// Blocks disappear the moment they lock.
+    Invisible,
+    /// Blocks disappear this many microseconds after they lock, going by [`Tetris::pass_time`].
+    Fading(u128),
+    /// Locked blocks are shown as [`BlockType::Outline`] blocks, so only their outlines show.
+    Outline,
+}
+
+/// What locking the last piece achieved.
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct LockResult {
+    /// How many lines the lock cleared. In big mode a line is a mino, two rows, tall.
+    pub cleared_lines: u8,
+    /// Which rows were cleared, counted from the top of the board as it was when the piece locked.
+    pub cleared_rows: Vec<usize>,
+    /// Whether the piece was spun into place.
+    pub t_spin: TSpin,
+    /// The combo after this lock: how many line-clearing locks in a row led up to it, not counting
+    /// the first. `None` if it didn't clear anything.
+    pub combo: Option<u32>,
+    /// The back-to-back chain after this lock: how many difficult clears in a row led up to it,
+    /// not counting the first. `None` once an easy clear has broken the chain. Locks that clear
+    /// nothing leave it alone.
+    pub back_to_back: Option<u32>,
+    /// Whether the lock cleared lines and left the board empty.
+    pub is_perfect_clear: bool,
+    /// The garbage lines the clear was worth, going by the [`AttackTable`].
+    pub attack: u32,
+    /// What was left of `attack` after cancelling incoming garbage, and so went to the opponent.
+    pub garbage_sent: u32,
+}
+
+impl LockResult {
+    /// Tetrises and line-clearing T-spins, the clears that keep a back-to-back chain going.
+    pub fn is_difficult(&self) -> bool {
+        return self.cleared_lines == 4 || (self.cleared_lines > 0 && self.t_spin != TSpin::None);
+    }
+
+    /// Whether this is a difficult clear following another, which usually earns a bonus.
+    pub fn is_back_to_back(&self) -> bool {
+        return self.is_difficult() && self.back_to_back.is_some_and(|back_to_back| back_to_back > 0);
+    }
+}
+
+/// A single action a player can take on the active piece.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Input {
+    /// Move one column left.
+    Left,
+    /// Move one column right.
+    Right,
+    /// Keep moving left until blocked, as when holding the key past the auto-repeat delay.
+    DasLeft,
+    /// Keep moving right until blocked, as when holding the key past the auto-repeat delay.
+    DasRight,
+    /// Move one row down without locking.
+    SoftDrop,
+    /// Keep moving down until blocked, without locking.
+    SonicDrop,
+    /// Rotate clockwise, with kicks.
+    RotateClockwise,
+    /// Rotate counterclockwise, with kicks.
+    RotateCounterclockwise,
+    /// Swap with the held piece.
+    Hold,
+    /// Drop to the bottom and lock.
+    HardDrop,
+}
+
+/// A direction to rotate a piece in.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Rotation {
+    /// A quarter turn clockwise.
+    Clockwise,
+    /// A quarter turn counterclockwise.
+    Counterclockwise,
+}
+
+/// Offsets tried in order when a clockwise rotation doesn't fit where it is.
+const CLOCKWISE_KICKS: [Vector2; 5] = [
+    Vector2 { x: -1, y:  0 },
+    Vector2 { x:  1, y:  0 },
+    Vector2 { x:  0, y:  1 },
+    Vector2 { x: -1, y:  1 },
+    Vector2 { x:  1, y: -1 },
+];
+
+/// Offsets tried in order when a counterclockwise rotation doesn't fit where it is.
+const COUNTERCLOCKWISE_KICKS: [Vector2; 5] = [
+    Vector2 { x: -1, y:  0 },
+    Vector2 { x:  1, y:  0 },
+    Vector2 { x:  0, y:  1 },
+    Vector2 { x:  1, y:  1 },
+    Vector2 { x: -1, y: -1 },
+];
+
+/// How a rotation that doesn't fit where it is gets resolved.
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RotationSystem {
+    /// Tries a few nearby offsets before giving up, which is what makes T-spins and tucks possible.
+    Kicks,
+    /// Never moves the piece to make a rotation fit, like the classic console games.
+    Classic,
+}
+
+impl RotationSystem {
+    /// Offsets tried in order when a rotation doesn't fit where it is.
+    pub fn kicks(&self, rotation: Rotation) -> &'static [Vector2] {
+        return match (self, rotation) {
+            (RotationSystem::Classic, _)                        => &[],
+            (RotationSystem::Kicks, Rotation::Clockwise)        => &CLOCKWISE_KICKS,
+            (RotationSystem::Kicks, Rotation::Counterclockwise) => &COUNTERCLOCKWISE_KICKS,
+        }
+    }
+}
+
+/// How many times moving or rotating a grounded piece restarts its lock delay, so a piece
+/// can't be kept from locking forever.
+pub const MAX_LOCK_RESETS: u32 = 15;
+
+/// An immutable copy of everything a player can see, for bots and other tools.
+///
+/// Unlike [`TetrisState`], the board does not have the active piece drawn onto it.
+#[derive(Clone)]
+pub struct GameSnapshot {
+    /// The locked blocks.
+    pub board: Board,
+    /// The piece currently being controlled.
+    pub active_piece: Piece,
+    /// The piece in the hold slot, if any.
+    pub held_piece: Option<PieceType>,
+    /// Whether [`Input::Hold`] would do anything right now.
+    pub can_hold_piece: bool,
+    /// The upcoming pieces, soonest first.
+    pub next_pieces: Vec<PieceType>,
+    /// How the active piece rotates.
+    pub rotation_system: RotationSystem,
+}
+
+impl GameSnapshot {
+    /// The piece that would become active after [`Input::Hold`], already at its spawn point,
+    /// or `None` if holding isn't possible.
+    pub fn piece_after_hold(&self) -> Option<Piece> {
+        if !self.can_hold_piece {
+            return None;
+        }
+
+        let piece_type = match self.held_piece {
+            Some(held_piece) => held_piece,
+            None => *self.next_pieces.first()?,
+        };
+
+        let mut piece = Piece::from_piece_type(&piece_type);
+        Tetris::move_piece_to_spawn_point(&mut piece, &self.board);
+
+        if is_invalid_state(&piece, &self.board) {
+            return None;
+        }
+
+        return Some(piece);
+    }
+
+    /// Lists every distinct place the active piece can end up, including the alternatives
+    /// opened up by holding. See [`find_placements`] for what counts as reachable.
+    ///
+    /// Needs at least one piece of preview to know what holding into an empty slot would give.
+    pub fn reachable_placements(&self) -> Vec<Placement> {
+        let mut placements = find_placements(&self.board, &self.active_piece, self.rotation_system);
+
+        if let Some(piece_after_hold) = self.piece_after_hold() {
+            for mut placement in find_placements(&self.board, &piece_after_hold, self.rotation_system) {
+                let is_duplicate = placement.piece.block_type == self.active_piece.block_type
+                    && placements.iter().any(|existing| existing.covers_same_cells(&placement));
+
+                if !is_duplicate {
+                    placement.inputs.insert(0, Input::Hold);
+                    placements.push(placement);
+                }
+            }
+        }
+
+        return placements;
+    }
+}
+
+/// The rules engine: the board, the active piece, the hold slot and the piece sequence.
+///
+/// The engine has no notion of time beyond what it's told through [`Tetris::pass_time`]. Gravity
+/// is applied by calling [`Tetris::move_down_and_stick`], typically driven by a [`crate::ticker::Ticker`].
+pub struct Tetris {
+    sequence_index: usize,
+    can_hold_piece: bool,
+    sequence: PieceSequence,
+    active_piece: Piece,
+    held_piece: Option<PieceType>,
+    last_locked_piece: Option<Piece>,
+    last_lock_result: Option<LockResult>,
+    pieces_locked: u32,
+    combo: Option<u32>,
+    back_to_back: Option<u32>,
+    attack_table: AttackTable,
+    // Batches of garbage lines still to come up the board, oldest first
+    incoming_garbage: VecDeque<u32>,
+    outgoing_garbage: u32,
+    // Kept apart from the piece sequence, so taking garbage doesn't change the pieces
+    garbage_rng: StdRng,
+    // Whether the active piece got where it is by rotating, which is what makes a T-spin
+    last_move_was_rotation: bool,
+    line_clear_delay: u128,
+    clearing_rows: Vec<usize>,
+    preview_length: usize,
+    stack_visibility: StackVisibility,
+    flash_stack: bool,
+    // Microseconds of play so far, as told by `pass_time`
+    clock: u128,
+    last_line_clear_time: Option<u128>,
+    topped_out: bool,
+    rotation_system: RotationSystem,
+    lock_delay: u128,
+    // When the active piece last came to rest on something, going by `clock`
+    grounded_at: Option<u128>,
+    lock_resets: u32,
+    show_ghost: bool,
+    hold_enabled: bool,
+    board: Board,
+}
+
+impl Default for Tetris {
+    fn default() -> Tetris {
+        return Tetris::new();
+    }
+}
+
+impl Tetris {
+    /// Starts a new game with a 7-bag randomizer and a random seed.
+    pub fn new() -> Tetris {
+        return Tetris::with_seed(Randomizer::SevenBag, rand::random());
+    }
+
+    /// Starts a new game whose piece sequence is fully determined by the randomizer and the seed.
+    pub fn with_seed(randomizer: Randomizer, seed: u64) -> Tetris {
+        return Tetris::with_board_size(randomizer, seed, DEFAULT_BOARD_WIDTH, DEFAULT_VISIBLE_BOARD_HEIGHT, false);
+    }
+
+    /// Like [`Tetris::with_seed`], on a playfield `width` blocks wide with `visible_height` rows showing.
+    /// Sizes outside [`MIN_BOARD_WIDTH`]..=[`MAX_BOARD_WIDTH`] and
+    /// [`MIN_VISIBLE_BOARD_HEIGHT`]..=[`MAX_VISIBLE_BOARD_HEIGHT`] are clamped into range.
+    ///
+    /// In `big` mode every mino covers 2x2 blocks, so pieces move two columns at a time and
+    /// clear rows in pairs, each pair counting as one line. Sizes are rounded down to even numbers of blocks to fit.
+    pub fn with_board_size(randomizer: Randomizer, seed: u64, width: usize, visible_height: usize, big: bool) -> Tetris {
+        let mino_size = if big { 2 } else { 1 };
+        let board = Board::new(
+            width.clamp(mino_size * MIN_BOARD_WIDTH, MAX_BOARD_WIDTH) / mino_size * mino_size,
+            visible_height.clamp(MIN_VISIBLE_BOARD_HEIGHT, MAX_VISIBLE_BOARD_HEIGHT) / mino_size * mino_size,
+            mino_size,
+        );
+
+        let sequence_index: usize = 0;
+        let sequence = randomizer.make_sequence(&mut StdRng::seed_from_u64(seed));
+        let active_piece = Piece::from_piece_type(&PieceType::I);
+
+        let mut game = Tetris {
+            sequence_index,
+            sequence,
+            active_piece,
+            held_piece: None,
+            last_locked_piece: None,
+            last_lock_result: None,
+            pieces_locked: 0,
+            combo: None,
+            back_to_back: None,
+            attack_table: AttackTable::default(),
+            incoming_garbage: VecDeque::new(),
+            outgoing_garbage: 0,
+            garbage_rng: StdRng::seed_from_u64(seed.wrapping_add(1)),
+            last_move_was_rotation: false,
+            line_clear_delay: 0,
+            clearing_rows: Vec::new(),
+            preview_length: DEFAULT_PREVIEW_LENGTH,
+            stack_visibility: StackVisibility::Visible,
+            flash_stack: false,
+            clock: 0,
+            last_line_clear_time: None,
+            topped_out: false,
+            rotation_system: RotationSystem::Kicks,
+            lock_delay: 0,
+            grounded_at: None,
+            lock_resets: 0,
+            show_ghost: true,
+            hold_enabled: true,
+            can_hold_piece: true,
+            board,
+        };
+
+        game.spawn_next_piece();
+
+        return game;
+    }
+
+    fn get_piece_from_sequence(sequence: &PieceSequence, index: usize) -> &PieceType {
+        return &sequence[index % sequence.len()];
+    }
+
+    // Pieces spawn left of centre, resting on the row just above the visible field, then drop
+    // straight into view if there's room, as the guideline has it
+    pub(crate) fn move_piece_to_spawn_point(piece: &mut Piece, board: &Board) {
+        let mino_size = board.mino_size();
+        let lowest_block = piece.blocks().iter().map(|block| block.y).max().unwrap();
+        piece.position.x = ((board.width() / mino_size - 1) / 2) as i8;
+        piece.position.y = (board.buffer_height() / mino_size - 1) as i8 - lowest_block;
+
+        if let Some(dropped_piece) = try_and_move_piece(piece, board, Vector2 { x: 0, y: 1 }) {
+            *piece = dropped_piece;
+        }
+    }
+
+    fn spawn_next_piece(self: &mut Tetris) {
+        let piece_type = Tetris::get_piece_from_sequence(&self.sequence, self.sequence_index);
+        self.active_piece = Piece::from_piece_type(piece_type);
+        Tetris::move_piece_to_spawn_point(&mut self.active_piece, &self.board);
+        self.sequence_index += 1;
+        self.can_hold_piece = true;
+        self.last_move_was_rotation = false;
+        self.grounded_at = None;
+        self.lock_resets = 0;
+    }
+
+    // Moving or rotating a piece that's resting on something gives it a fresh lock delay
+    fn restart_lock_delay(self: &mut Tetris) {
+        if self.grounded_at.is_some() && self.lock_resets < MAX_LOCK_RESETS {
+            self.grounded_at = None;
+            self.lock_resets += 1;
+        }
+    }
+
+    /// Swaps the active piece with the held one, or stashes it if nothing is held yet.
+    /// Only allowed once per spawned piece, and not at all if holding is disabled.
+    pub fn hold_piece(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() || !self.hold_enabled {
+            return NothingSpecial;
+        }
+
+        match self.held_piece {
+            None => {
+                self.held_piece = Some(PieceType::from_block_type(&self.active_piece.block_type));
+                self.spawn_next_piece();
+                self.can_hold_piece = false;
+            }
+            Some(held_piece) => {
+                if self.can_hold_piece {
+                    let new_active_piece = Piece::from_piece_type(&held_piece);
+                    self.held_piece = Some(PieceType::from_block_type(&self.active_piece.block_type));
+                    self.active_piece = new_active_piece;
+                    Tetris::move_piece_to_spawn_point(&mut self.active_piece, &self.board);
+                    self.can_hold_piece = false;
+                    self.last_move_was_rotation = false;
+                    self.grounded_at = None;
+                    self.lock_resets = 0;
+                }
+            }
+        }
+
+        return SpawnedNewPieceAndClearedLines(0);
+    }
+
+    /// Rotates the active piece clockwise, kicking it into place if the rotation system allows.
+    pub fn try_and_rotate_clockwise(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        if let Some(rotated_piece) = try_and_rotate_piece(&self.active_piece, &self.board, Rotation::Clockwise, self.rotation_system) {
+            self.active_piece = rotated_piece;
+            self.last_move_was_rotation = true;
+            self.restart_lock_delay();
+        }
+
+        return NothingSpecial;
+    }
+
+    /// Rotates the active piece counterclockwise, kicking it into place if the rotation system allows.
+    pub fn try_and_rotate_counterclockwise(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        if let Some(rotated_piece) = try_and_rotate_piece(&self.active_piece, &self.board, Rotation::Counterclockwise, self.rotation_system) {
+            self.active_piece = rotated_piece;
+            self.last_move_was_rotation = true;
+            self.restart_lock_delay();
+        }
+
+        return NothingSpecial;
+    }
+
+    /// Moves the active piece one column left, if there is room.
+    pub fn try_and_move_left(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        if let Some(moved_piece) = try_and_move_piece(&self.active_piece, &self.board, Vector2 { x: -1, y: 0 }) {
+            self.active_piece = moved_piece;
+            self.last_move_was_rotation = false;
+            self.restart_lock_delay();
+        }
+
+        return NothingSpecial;
+    }
+
+    /// Moves the active piece one column right, if there is room.
+    pub fn try_and_move_right(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        if let Some(moved_piece) = try_and_move_piece(&self.active_piece, &self.board, Vector2 { x: 1, y: 0 }) {
+            self.active_piece = moved_piece;
+            self.last_move_was_rotation = false;
+            self.restart_lock_delay();
+        }
+
+        return NothingSpecial;
+    }
+
+    /// Moves the active piece one row down, if there is room. Never locks the piece.
+    pub fn try_and_move_down(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        if let Some(moved_piece) = try_and_move_piece(&self.active_piece, &self.board, Vector2 { x: 0, y: 1 }) {
+            self.active_piece = moved_piece;
+            self.last_move_was_rotation = false;
+        }
+
+        return NothingSpecial
+    }
+
+    /// Performs a single [`Input`] on the active piece.
+    pub fn apply_input(self: &mut Tetris, input: Input) -> MoveOutcome {
+        return match input {
+            Input::Left                   => self.try_and_move_left(),
+            Input::Right                  => self.try_and_move_right(),
+            Input::DasLeft                => self.slide_active_piece(Vector2 { x: -1, y: 0 }),
+            Input::DasRight               => self.slide_active_piece(Vector2 { x: 1, y: 0 }),
+            Input::SoftDrop               => self.try_and_move_down(),
+            Input::SonicDrop              => self.slide_active_piece(Vector2 { x: 0, y: 1 }),
+            Input::RotateClockwise        => self.try_and_rotate_clockwise(),
+            Input::RotateCounterclockwise => self.try_and_rotate_counterclockwise(),
+            Input::Hold                   => self.hold_piece(),
+            Input::HardDrop               => self.slam(),
+        }
+    }
+
+    fn slide_active_piece(self: &mut Tetris, direction: Vector2) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        let slid_piece = slide_piece(&self.active_piece, &self.board, direction);
+        if slid_piece.position != self.active_piece.position {
+            self.active_piece = slid_piece;
+            self.last_move_was_rotation = false;
+            self.restart_lock_delay();
+        }
+
+        return NothingSpecial;
+    }
+
+    /// Lists every distinct place the active piece can end up, including the alternatives
+    /// opened up by holding, each with the shortest sequence of inputs that gets there.
+    ///
+    /// The game itself is left untouched.
+    pub fn reachable_placements(&self) -> Vec<Placement> {
+        return self.snapshot(1).reachable_placements();
+    }
+
+    /// Applies one step of gravity, locking the piece and spawning the next one if it can't fall any further.
+    ///
+    /// With a lock delay, gravity never locks the piece. [`Tetris::pass_time`] does once the delay runs out.
+    pub fn move_down_and_stick(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        self.active_piece.position.y += 1;
+
+        if is_invalid_state(&self.active_piece, &self.board) {
+            self.active_piece.position.y -= 1;
+
+            if self.lock_delay > 0 {
+                return NothingSpecial;
+            }
+
+            return self.lock_active_piece();
+        }
+
+        self.last_move_was_rotation = false;
+
+        return MoveOutcome::NothingSpecial;
+    }
+
+    /// Hard drops the active piece and locks it immediately.
+    pub fn slam(self: &mut Tetris) -> MoveOutcome {
+        if self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        if try_and_move_piece(&self.active_piece, &self.board, Vector2 { x: 0, y: 1 }).is_some() {
+            self.last_move_was_rotation = false;
+        }
+
+        loop {
+            self.active_piece.position.y += 1;
+
+            if is_invalid_state(&self.active_piece, &self.board) {
+                self.active_piece.position.y -=1;
+
+                return self.lock_active_piece();
+            }
+        }
+    }
+
+    fn lock_active_piece(self: &mut Tetris) -> MoveOutcome {
+        // Corners have to be checked before the piece and any cleared lines change the board
+        let t_spin = self.detect_t_spin();
+
+        // Locking a piece entirely inside the hidden buffer ends the game
+        let buffer_height = self.board.buffer_height() / self.board.mino_size();
+        let locked_out = self.active_piece.cells().iter().all(|cell| (cell.y as usize) < buffer_height);
+
+        Tetris::stick_piece_to_board(&self.active_piece, &mut self.board);
+        self.last_locked_piece = Some(self.active_piece);
+
+        for cell in self.active_piece.cells() {
+            for (row, column) in self.board.blocks_under(cell).collect::<Vec<(usize, usize)>>() {
+                self.board.blocks[row][column].locked_at = self.clock;
+            }
+        }
+
+        let full_rows = full_rows(&self.board);
+        let cleared_lines = (full_rows.len() / self.board.mino_size()) as u8;
+        if cleared_lines > 0 {
+            self.last_line_clear_time = Some(self.clock);
+        }
+
+        // Whatever isn't about to be cleared has to be empty already
+        let is_perfect_clear = cleared_lines > 0 && self.board.blocks
+            .iter()
+            .enumerate()
+            .filter(|(row, _)| !full_rows.contains(row))
+            .all(|(_, line)| line.iter().all(|block| block.block_type == BlockType::Empty));
+
+        let mut lock_result = LockResult {
+            cleared_lines,
+            cleared_rows: full_rows.clone(),
+            t_spin,
+            combo: None,
+            back_to_back: self.back_to_back,
+            is_perfect_clear,
+            attack: 0,
+            garbage_sent: 0,
+        };
+        if cleared_lines > 0 {
+            lock_result.combo = Some(self.combo.map_or(0, |combo| combo + 1));
+            lock_result.back_to_back = match lock_result.is_difficult() {
+                true  => Some(self.back_to_back.map_or(0, |back_to_back| back_to_back + 1)),
+                false => None,
+            };
+        }
+        self.combo = lock_result.combo;
+        self.back_to_back = lock_result.back_to_back;
+
+        lock_result.attack = self.attack_table.attack(&lock_result);
+        lock_result.garbage_sent = lock_result.attack - self.cancel_garbage(lock_result.attack);
+        self.outgoing_garbage += lock_result.garbage_sent;
+
+        self.last_lock_result = Some(lock_result);
+        self.pieces_locked += 1;
+
+        if locked_out {
+            self.topped_out = true;
+            return MoveOutcome::GameOver;
+        }
+
+        // Garbage only comes up after locks that clear nothing, and pushing the stack out the
+        // top of the board ends the game
+        if cleared_lines == 0 && self.raise_garbage() {
+            self.topped_out = true;
+            return MoveOutcome::GameOver;
+        }
+
+        if self.line_clear_delay > 0 && cleared_lines > 0 {
+            self.clearing_rows = full_rows;
+            return MoveOutcome::ClearingLines(cleared_lines);
+        }
+
+        Tetris::clear_full_lines(&mut self.board);
+
+        return self.spawn_after_lock(cleared_lines);
+    }
+
+    // Takes up to `attack` lines off the incoming garbage, oldest first, and returns how many
+    fn cancel_garbage(self: &mut Tetris, attack: u32) -> u32 {
+        let mut cancelled = 0;
+
+        while let Some(lines) = self.incoming_garbage.front_mut() {
+            let cancelling = (*lines).min(attack - cancelled);
+            *lines -= cancelling;
+            cancelled += cancelling;
+
+            if *lines > 0 {
+                break;
+            }
+            self.incoming_garbage.pop_front();
+        }
+
+        return cancelled;
+    }
+
+    // Pushes every pending batch up from the bottom of the board, each a solid block of rows
+    // with one gap in the same column. Returns whether any locked blocks went out the top.
+    fn raise_garbage(self: &mut Tetris) -> bool {
+        let mino_size = self.board.mino_size();
+        let width = self.board.width();
+        let mut overflowed = false;
+
+        while let Some(lines) = self.incoming_garbage.pop_front() {
+            let gap = self.garbage_rng.gen_range(0..width / mino_size);
+
+            for _ in 0..lines as usize * mino_size {
+                let top_row = self.board.blocks.remove(0);
+                overflowed |= top_row.iter().any(|block| block.block_type != BlockType::Empty);
+
+                let row = (0..width)
+                    .map(|column| match column / mino_size == gap {
+                        true  => Block::EMPTY,
+                        false => Block { block_type: BlockType::Garbage, locked_at: self.clock },
+                    })
+                    .collect();
+                self.board.blocks.push(row);
+            }
+        }
+
+        return overflowed;
+    }
+
+    fn spawn_after_lock(self: &mut Tetris, cleared_lines: u8) -> MoveOutcome {
+        self.spawn_next_piece();
+
+        if is_invalid_state(&self.active_piece, &self.board) {
+            self.topped_out = true;
+            return MoveOutcome::GameOver;
+        }
+
+        return MoveOutcome::SpawnedNewPieceAndClearedLines(cleared_lines);
+    }
+
+    /// Sets how long, in microseconds, frontends should leave full lines on the board before
+    /// removing them. Zero, the default, removes them the moment the piece locks.
+    ///
+    /// The engine doesn't keep time itself. While lines are clearing, every action is ignored
+    /// until the frontend calls [`Tetris::finish_line_clear`].
+    pub fn set_line_clear_delay(self: &mut Tetris, line_clear_delay: u128) {
+        self.line_clear_delay = line_clear_delay;
+    }
+
+    /// Sets how many upcoming pieces [`Tetris::state`] previews, up to [`MAX_PREVIEW_LENGTH`].
+    pub fn set_preview_length(self: &mut Tetris, preview_length: usize) {
+        self.preview_length = preview_length.min(MAX_PREVIEW_LENGTH);
+    }
+
+    /// How many upcoming pieces [`Tetris::state`] previews.
+    pub fn preview_length(&self) -> usize {
+        return self.preview_length;
+    }
+
+    /// Sets how much of the stack [`Tetris::state`] shows. The whole stack is shown again once the game is over.
+    pub fn set_stack_visibility(self: &mut Tetris, stack_visibility: StackVisibility) {
+        self.stack_visibility = stack_visibility;
+    }
+
+    /// Sets whether clearing lines briefly shows the whole stack when it's otherwise hidden.
+    pub fn set_flash_stack(self: &mut Tetris, flash_stack: bool) {
+        self.flash_stack = flash_stack;
+    }
+
+    /// Sets how the active piece rotates. Defaults to [`RotationSystem::Kicks`].
+    pub fn set_rotation_system(self: &mut Tetris, rotation_system: RotationSystem) {
+        self.rotation_system = rotation_system;
+    }
+
+    /// How the active piece rotates.
+    pub fn rotation_system(&self) -> RotationSystem {
+        return self.rotation_system;
+    }
+
+    /// Sets how long, in microseconds, a piece can rest on the stack before it locks. Moving or
+    /// rotating it starts the wait again, up to [`MAX_LOCK_RESETS`] times per piece.
+    ///
+    /// Zero, the default, locks the piece on the first step of gravity it can't take.
+    pub fn set_lock_delay(self: &mut Tetris, lock_delay: u128) {
+        self.lock_delay = lock_delay;
+    }
+
+    /// Sets whether [`Tetris::state`] draws the ghost piece.
+    pub fn set_show_ghost(self: &mut Tetris, show_ghost: bool) {
+        self.show_ghost = show_ghost;
+    }
+
+    /// Sets whether [`Tetris::hold_piece`] is allowed at all.
+    pub fn set_hold_enabled(self: &mut Tetris, hold_enabled: bool) {
+        self.hold_enabled = hold_enabled;
+    }
+
+    /// Tells the engine how much time has passed, in microseconds, for the rules that go by time:
+    /// fading stacks, stack flashes and the lock delay. Gravity still comes from outside.
+    ///
+    /// Returns what locking the piece did if its lock delay ran out.
+    pub fn pass_time(self: &mut Tetris, delta_time: u128) -> MoveOutcome {
+        self.clock += delta_time;
+
+        if self.lock_delay == 0 || self.is_clearing_lines() || self.topped_out {
+            return NothingSpecial;
+        }
+
+        let is_grounded = try_and_move_piece(&self.active_piece, &self.board, Vector2 { x: 0, y: 1 }).is_none();
+        if !is_grounded {
+            self.grounded_at = None;
+            return NothingSpecial;
+        }
+
+        let grounded_at = *self.grounded_at.get_or_insert(self.clock);
+        if self.clock - grounded_at >= self.lock_delay {
+            return self.lock_active_piece();
+        }
+
+        return NothingSpecial;
+    }
+
+    /// How long full lines should stay on the board, in microseconds.
+    pub fn line_clear_delay(&self) -> u128 {
+        return self.line_clear_delay;
+    }
+
+    /// Whether full lines are waiting for [`Tetris::finish_line_clear`].
+    pub fn is_clearing_lines(&self) -> bool {
+        return !self.clearing_rows.is_empty();
+    }
+
+    /// Removes the lines left by [`MoveOutcome::ClearingLines`] and spawns the next piece.
+    pub fn finish_line_clear(self: &mut Tetris) -> MoveOutcome {
+        if !self.is_clearing_lines() {
+            return NothingSpecial;
+        }
+
+        let cleared_lines = Tetris::clear_full_lines(&mut self.board) / self.board.mino_size() as u8;
+        self.clearing_rows.clear();
+
+        return self.spawn_after_lock(cleared_lines);
+    }
+
+    // A T that rotated into place with at least three of the four cells diagonal to its centre
+    // filled (walls and floor count). It's a full T-spin if both corners it points at are filled.
+    fn detect_t_spin(&self) -> TSpin {
+        if self.active_piece.block_type != BlockType::T || !self.last_move_was_rotation {
+            return TSpin::None;
+        }
+
+        let centre = self.active_piece.position;
+        let is_filled = |x: i8, y: i8| {
+            let cell = Vector2 { x: centre.x + x, y: centre.y + y };
+            return !self.board.contains(cell) || self.board.is_occupied(cell);
+        };
+
+        let filled_corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .iter()
+            .filter(|(x, y)| is_filled(*x, *y))
+            .count();
+
+        if filled_corners < 3 {
+            return TSpin::None;
+        }
+
+        // The blocks of every T rotation are ordered so the one it points with comes third
+        let pointing = self.active_piece.blocks()[2];
+        let front_corners = if pointing.x == 0 {
+            [(-1, pointing.y), (1, pointing.y)]
+        } else {
+            [(pointing.x, -1), (pointing.x, 1)]
+        };
+
+        if front_corners.iter().all(|(x, y)| is_filled(*x, *y)) {
+            return TSpin::Full;
+        }
+
+        return TSpin::Mini;
+    }
+
+    // Returns how many rows it cleared, which in big mode is twice the lines
+    pub(crate) fn clear_full_lines(board: &mut Board) -> u8 {
+        let mut cleared_lines: u8 = 0;
+
+        // Scan all the lines down
+        for line_index in 0..board.blocks.len() {
+            let line = board.blocks[line_index].clone();
+
+            let mut line_is_full = true;
+            for block in line.iter() {
+                if block.block_type == BlockType::Empty {
+                    line_is_full = false;
+                }
+            }
+
+            if line_is_full {
+                // Move all the lines above one down.
+                for upper_line_index in (1..=line_index).rev() {
+                    for column_index in 0..line.len() {
+                        board.blocks[upper_line_index][column_index] = board.blocks[upper_line_index - 1][column_index];
+                    }
+                }
+
+                // Clear the top line
+                for column_index in 0..line.len() {
+                    board.blocks[0][column_index] = Block::EMPTY;
+                }
+
+                cleared_lines += 1;
+            }
+        }
+
+        return cleared_lines;
+    }
+
+    pub(crate) fn stick_piece_to_board(piece: &Piece, board: &mut Board) {
+        for cell in piece.cells() {
+            for (row, column) in board.blocks_under(cell) {
+                board.blocks[row][column].block_type = piece.block_type;
+            }
+        }
+    }
+
+    /// The locked blocks, without the active piece.
+    pub fn board(&self) -> &Board {
+        return &self.board;
+    }
+
+    /// The piece currently being controlled.
+    pub fn active_piece(&self) -> &Piece {
+        return &self.active_piece;
+    }
+
+    /// Whether holding is allowed for the active piece.
+    pub fn can_hold_piece(&self) -> bool {
+        return self.hold_enabled && self.can_hold_piece;
+    }
+
+    /// The most recently locked piece, exactly where it locked.
+    pub fn last_locked_piece(&self) -> Option<&Piece> {
+        return self.last_locked_piece.as_ref();
+    }
+
+    /// What locking the most recent piece achieved.
+    pub fn last_lock_result(&self) -> Option<&LockResult> {
+        return self.last_lock_result.as_ref();
+    }
+
+    /// How many pieces have locked since the game started.
+    pub fn pieces_locked(&self) -> u32 {
+        return self.pieces_locked;
+    }
+
+    /// How many line-clearing locks in a row there have been, not counting the first, or `None`
+    /// if the last lock didn't clear anything.
+    pub fn combo(&self) -> Option<u32> {
+        return self.combo;
+    }
+
+    /// How many difficult clears in a row there have been, not counting the first, or `None` if
+    /// there hasn't been one since the last easy clear. See [`LockResult::is_difficult`].
+    pub fn back_to_back(&self) -> Option<u32> {
+        return self.back_to_back;
+    }
+
+    /// Sets how much garbage each kind of clear sends. Defaults to [`AttackTable::default`].
+    pub fn set_attack_table(self: &mut Tetris, attack_table: AttackTable) {
+        self.attack_table = attack_table;
+    }
+
+    /// Queues garbage from an opponent. It's cancelled by this board's own attacks as they happen,
+    /// and whatever is left comes up the board after the next lock that clears nothing, with a gap
+    /// somewhere in every batch. In big mode every line is a mino tall.
+    pub fn receive_garbage(self: &mut Tetris, lines: u32) {
+        if lines > 0 {
+            self.incoming_garbage.push_back(lines);
+        }
+    }
+
+    /// How many garbage lines are waiting to come up the board.
+    pub fn pending_garbage(&self) -> u32 {
+        return self.incoming_garbage.iter().sum();
+    }
+
+    /// Hands over the garbage sent since the last call, for the frontend to pass to an opponent
+    /// with [`Tetris::receive_garbage`].
+    pub fn take_outgoing_garbage(self: &mut Tetris) -> u32 {
+        return std::mem::take(&mut self.outgoing_garbage);
+    }
+
+    /// Removes every locked block, leaving the active piece where it is. Useful for drills and puzzles.
+    pub fn clear_board(self: &mut Tetris) {
+        for line in self.board.blocks.iter_mut() {
+            for block in line.iter_mut() {
+                block.block_type = BlockType::Empty;
+            }
+        }
+    }
+
+    /// Takes an immutable copy of the game, previewing `preview_length` upcoming pieces.
+    pub fn snapshot(&self, preview_length: usize) -> GameSnapshot {
+        let next_pieces = (0..preview_length)
+            .map(|offset| *Tetris::get_piece_from_sequence(&self.sequence, self.sequence_index + offset))
+            .collect();
+
+        return GameSnapshot {
+            board: self.board.clone(),
+            active_piece: self.active_piece,
+            held_piece: self.held_piece,
+            can_hold_piece: self.can_hold_piece(),
+            next_pieces,
+            rotation_system: self.rotation_system,
+        }
+    }
+
+    // The locked blocks as the stack visibility rules let the player see them
+    fn visible_board(&self) -> Board {
+        let mut board = self.board.clone();
+
+        let is_flashing = self.flash_stack && (self.is_clearing_lines()
+            || self.last_line_clear_time.is_some_and(|time| self.clock - time < STACK_FLASH_TIME));
+
+        if self.stack_visibility == StackVisibility::Visible || self.topped_out || is_flashing {
+            return board;
+        }
+
+        for line in board.blocks.iter_mut() {
+            for block in line.iter_mut().filter(|block| block.block_type != BlockType::Empty) {
+                block.block_type = match self.stack_visibility {
+                    StackVisibility::Fading(time) if self.clock - block.locked_at < time => block.block_type,
+                    StackVisibility::Outline => BlockType::Outline,
+                    _                        => BlockType::Empty,
+                };
+            }
+        }
+
+        return board;
+    }
+
+    /// Builds a render-ready picture of the current game, with the stack hidden as the
+    /// [`StackVisibility`] says.
+    ///
+    /// While lines are clearing there is no active piece, so neither it nor its ghost is drawn.
+    pub fn state(&self) -> TetrisState {
+        let next_pieces_board = NextPiecesBoard::from_sequence(&self.sequence, &self.sequence_index, self.preview_length);
+        let held_piece_board = HeldPieceBoard::from_piece_type(&self.held_piece, self.can_hold_piece);
+
+        let mut board = self.visible_board();
+
+        if !self.is_clearing_lines() {
+            if self.show_ghost {
+                let ghost_piece: Piece = calculate_and_create_ghost_piece(&self.active_piece, &self.board);
+                Tetris::stick_piece_to_board(&ghost_piece, &mut board);
+            }
+            Tetris::stick_piece_to_board(&self.active_piece, &mut board);
+        }
+
+        return TetrisState {
+            board,
+            held_piece_board,
+            next_pieces_board,
+            hints: Vec::new(),
+            pieces_locked: self.pieces_locked,
+            last_locked_piece: self.last_locked_piece,
+            clearing_rows: self.clearing_rows.clone(),
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+            pending_garbage: self.pending_garbage(),
+        }
+    }
+}
+
+
+/// The playfield: the visible rows with as many hidden rows above them again, for pieces to spawn into
+/// and stacks to grow into. Row 0 is the top of the hidden buffer.
+///
+/// Pieces are positioned in minos, which cover [`Board::mino_size`] blocks each way.
+/// Everything measured in rows and columns is in blocks.
+#[derive(Clone)]
+pub struct Board {
+    /// The cells, indexed as `blocks[row][column]`.
+    pub blocks: Vec<Vec<Block>>,
+    mino_size: usize,
+}
+
+impl Board {
+    /// An empty board `width` blocks wide, with `visible_height` rows showing, for minos covering
+    /// `mino_size` by `mino_size` blocks. Both sizes have to be multiples of `mino_size`.
+    pub fn new(width: usize, visible_height: usize, mino_size: usize) -> Board {
+        return Board {
+            blocks: vec![vec![Block::EMPTY; width]; 2 * visible_height],
+            mino_size,
+        }
+    }
+
+    /// How many blocks each mino covers, across and down: 1, or 2 in big mode.
+    pub fn mino_size(&self) -> usize {
+        return self.mino_size;
+    }
+
+    /// Whether the mino at `cell` is inside the board.
+    pub fn contains(&self, cell: Vector2) -> bool {
+        return cell.x >= 0
+            && cell.y >= 0
+            && (cell.x as usize + 1) * self.mino_size <= self.width()
+            && (cell.y as usize + 1) * self.mino_size <= self.height();
+    }
+
+    /// Whether any block under the mino at `cell` is filled. The mino has to be inside the board.
+    pub fn is_occupied(&self, cell: Vector2) -> bool {
+        return self.blocks_under(cell).any(|(row, column)| self.blocks[row][column].block_type != BlockType::Empty);
+    }
+
+    /// The `(row, column)` of every block under the mino at `cell`. The mino has to be inside the board.
+    pub fn blocks_under(&self, cell: Vector2) -> impl Iterator<Item = (usize, usize)> {
+        let mino_size = self.mino_size;
+        let top = cell.y as usize * mino_size;
+        let left = cell.x as usize * mino_size;
+        return (top..top + mino_size).flat_map(move |row| (left..left + mino_size).map(move |column| (row, column)));
+    }
+
+    /// The mino that the block at `row`, `column` belongs to.
+    pub fn mino_at(&self, row: usize, column: usize) -> Vector2 {
+        return Vector2 { x: (column / self.mino_size) as i8, y: (row / self.mino_size) as i8 };
+    }
+
+    /// Width in blocks.
+    pub fn width(&self) -> usize {
+        return self.blocks[0].len();
+    }
+
+    /// Height in blocks, hidden buffer included.
+    pub fn height(&self) -> usize {
+        return self.blocks.len();
+    }
+
+    /// How many hidden rows there are above the visible field, which is also the index of its top row.
+    pub fn buffer_height(&self) -> usize {
+        return self.blocks.len() / 2;
+    }
+}
+
+/// A small board showing the held piece.
+pub struct HeldPieceBoard {
+    /// The cells, indexed as `blocks[row][column]`.
+    pub blocks: Vec<Vec<Block>>,
+    /// Rows to draw half a block to the right, so that pieces an odd number of blocks wide sit in the middle.
+    pub half_block_offsets: Vec<bool>,
+}
+
+impl HeldPieceBoard {
+    // The held piece is drawn as ghost blocks while it can't be swapped back in
+    fn from_piece_type(maybe_piece_type: &Option<PieceType>, can_hold_piece: bool) -> HeldPieceBoard {
+        let mut held_piece = maybe_piece_type.map(|piece_type| Piece::from_piece_type(&piece_type));
+
+        if let Some(held_piece) = &mut held_piece {
+            if !can_hold_piece {
+                held_piece.block_type = BlockType::Ghost;
+            }
+        }
+
+        let (blocks, half_block_offsets) = make_preview(&[held_piece]);
+
+        return HeldPieceBoard { blocks, half_block_offsets };
+    }
+}
+
+/// A small board showing the upcoming pieces, top to bottom.
+pub struct NextPiecesBoard {
+    /// The cells, indexed as `blocks[row][column]`. Has no rows at all when nothing is previewed.
+    pub blocks: Vec<Vec<Block>>,
+    /// Rows to draw half a block to the right, so that pieces an odd number of blocks wide sit in the middle.
+    pub half_block_offsets: Vec<bool>,
+}
+
+impl NextPiecesBoard {
+    fn from_sequence(sequence: &PieceSequence, sequence_index: &usize, preview_length: usize) -> NextPiecesBoard {
+        if preview_length == 0 {
+            return NextPiecesBoard { blocks: Vec::new(), half_block_offsets: Vec::new() };
+        }
+
+        let next_pieces: Vec<Option<Piece>> = (0..preview_length)
+            .map(|offset| Some(Piece::from_piece_type(Tetris::get_piece_from_sequence(sequence, sequence_index + offset))))
+            .collect();
+
+        let (blocks, half_block_offsets) = make_preview(&next_pieces);
+
+        return NextPiecesBoard { blocks, half_block_offsets };
+    }
+}
+
+// Lays pieces out one under the other, each centred in its own slot, with None leaving a slot empty
+fn make_preview(pieces: &[Option<Piece>]) -> (Vec<Vec<Block>>, Vec<bool>) {
+    let height = 1 + PREVIEW_SLOT_HEIGHT * pieces.len();
+    let mut blocks = vec![vec![Block::EMPTY; PREVIEW_WIDTH]; height];
+    let mut half_block_offsets = vec![false; height];
+
+    for (slot, piece) in pieces.iter().enumerate() {
+        let mut piece = match piece {
+            Some(piece) => *piece,
+            None => continue,
+        };
+
+        let min_x = piece.blocks().iter().map(|block| block.x).min().unwrap();
+        let max_x = piece.blocks().iter().map(|block| block.x).max().unwrap();
+        let min_y = piece.blocks().iter().map(|block| block.y).min().unwrap();
+
+        let spare_width = PREVIEW_WIDTH as i8 - (max_x - min_x + 1);
+        let slot_row = 1 + (PREVIEW_SLOT_HEIGHT * slot) as i8;
+        piece.position = Vector2 { x: spare_width / 2 - min_x, y: slot_row - min_y };
+
+        for cell in piece.cells() {
+            blocks[cell.y as usize][cell.x as usize].block_type = piece.block_type;
+            half_block_offsets[cell.y as usize] = spare_width % 2 == 1;
+        }
+    }
+
+    return (blocks, half_block_offsets);
+}
+
+fn full_rows(board: &Board) -> Vec<usize> {
+    return (0..board.blocks.len())
+        .filter(|row_index| board.blocks[*row_index].iter().all(|block| block.block_type != BlockType::Empty))
+        .collect();
+}
+
+/// A single cell of a board.
+#[derive(Copy)]
+#[derive(Clone)]
+pub struct Block {
+    /// What occupies the cell.
+    pub block_type: BlockType,
+    /// When the block locked, going by [`Tetris::pass_time`]. Zero for blocks that never locked.
+    pub locked_at: u128,
+}
+
+impl Block {
+    /// A cell with nothing in it.
+    pub const EMPTY: Block = Block { block_type: BlockType::Empty, locked_at: 0 };
+}
+
+
+fn calculate_and_create_ghost_piece(piece: &Piece, board: &Board) -> Piece {
+    let mut ghost_piece = *piece;
+    ghost_piece.block_type = BlockType::Ghost;
+
+    loop {
+        ghost_piece.position.y += 1;
+        if is_invalid_state(&ghost_piece, board)
+        {
+            ghost_piece.position.y -=1;
+            break;
+        };
+    }
+
+    return ghost_piece;
+}
+
+/// Returns a copy of `piece` moved by `offset`, or `None` if it wouldn't fit on `board`.
+pub fn try_and_move_piece(piece: &Piece, board: &Board, offset: Vector2) -> Option<Piece> {
+    let mut moved_piece = *piece;
+    moved_piece.position.x += offset.x;
+    moved_piece.position.y += offset.y;
+
+    if is_invalid_state(&moved_piece, board) {
+        return None;
+    }
+
+    return Some(moved_piece);
+}
+
+/// Returns a copy of `piece` rotated in the given direction, kicked into place as `rotation_system`
+/// allows, or `None` if no kick makes it fit on `board`.
+pub fn try_and_rotate_piece(piece: &Piece, board: &Board, rotation: Rotation, rotation_system: RotationSystem) -> Option<Piece> {
+    let mut rotated_piece = *piece;
+    match rotation {
+        Rotation::Clockwise        => rotated_piece.rotate_clockwise(),
+        Rotation::Counterclockwise => rotated_piece.rotate_counterclockwise(),
+    }
+    let kicks = rotation_system.kicks(rotation);
+
+    if !is_invalid_state(&rotated_piece, board) {
+        return Some(rotated_piece);
+    }
+
+    return kicks.iter().find_map(|kick| try_and_move_piece(&rotated_piece, board, *kick));
+}
+
+/// Returns a copy of `piece` moved one step at a time in `direction` until it's blocked.
+pub fn slide_piece(piece: &Piece, board: &Board, direction: Vector2) -> Piece {
+    let mut slid_piece = *piece;
+
+    while let Some(moved_piece) = try_and_move_piece(&slid_piece, board, direction) {
+        slid_piece = moved_piece;
+    }
+
+    return slid_piece;
+}
+
+/// Returns a copy of `piece` dropped as far down as it can go on `board`.
+pub fn drop_piece(piece: &Piece, board: &Board) -> Piece {
+    return slide_piece(piece, board, Vector2 { x: 0, y: 1 });
+}
+
+pub(crate) fn is_invalid_state(piece: &Piece, board: &Board) -> bool {
+    return piece_is_out_of_bounds(piece, board) || collisions_exist(piece, board);
+}
+
+fn piece_is_out_of_bounds (piece: &Piece, board: &Board) -> bool {
+    return piece.cells().iter().any(|cell| !board.contains(*cell));
+}
+
+fn collisions_exist(active_piece: &Piece, board: &Board) -> bool {
+    return active_piece.cells().iter().any(|cell| board.is_occupied(*cell));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stands in for the rest of the stack. Anything but garbage, so raised garbage stands out.
+    const FILLER: Block = Block { block_type: BlockType::J, locked_at: 0 };
+
+    fn give_piece(tetris: &mut Tetris, piece_type: PieceType) {
+        let mut piece = Piece::from_piece_type(&piece_type);
+        Tetris::move_piece_to_spawn_point(&mut piece, &tetris.board);
+        tetris.active_piece = piece;
+        tetris.last_move_was_rotation = false;
+    }
+
+    // Fills up the rows the active piece would land in, all but the cells it lands on
+    fn fill_around_landing(tetris: &mut Tetris) -> [Vector2; 4] {
+        let landing_cells = drop_piece(&tetris.active_piece, &tetris.board).cells();
+
+        for row in landing_cells.iter().map(|cell| cell.y as usize) {
+            for column in 0..tetris.board.width() {
+                let is_landing_cell = landing_cells.contains(&Vector2 { x: column as i8, y: row as i8 });
+                if !is_landing_cell && tetris.board.blocks[row][column].block_type == BlockType::Empty {
+                    tetris.board.blocks[row][column] = FILLER;
+                }
+            }
+        }
+
+        return landing_cells;
+    }
+
+    // Hard drops an I piece, flat for a single or upright for a tetris, into rows filled up around
+    // it. A block is left above the rows so the clear is never a perfect clear.
+    fn clear_lines(tetris: &mut Tetris, lines: u8) -> LockResult {
+        give_piece(tetris, PieceType::I);
+        if lines == 4 {
+            tetris.try_and_rotate_clockwise();
+        }
+
+        let landing_cells = fill_around_landing(tetris);
+        let row_above = landing_cells.iter().map(|cell| cell.y).min().unwrap() as usize - 1;
+        let column_beside = (0..tetris.board.width()).find(|column| landing_cells.iter().all(|cell| cell.x as usize != *column)).unwrap();
+        tetris.board.blocks[row_above][column_beside] = FILLER;
+
+        tetris.slam();
+        return tetris.last_lock_result().unwrap().clone();
+    }
+
+    fn lock_without_clearing(tetris: &mut Tetris) -> MoveOutcome {
+        give_piece(tetris, PieceType::O);
+        return tetris.slam();
+    }
+
+    fn count_blocks(tetris: &Tetris, block_type: BlockType) -> usize {
+        return tetris.board.blocks.iter().flatten().filter(|block| block.block_type == block_type).count();
+    }
+
+    #[test]
+    fn clears_in_a_row_build_a_combo_and_difficult_ones_a_back_to_back_chain() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+
+        let first_tetris = clear_lines(&mut tetris, 4);
+        assert_eq!(first_tetris.cleared_lines, 4);
+        assert_eq!(first_tetris.combo, Some(0));
+        assert_eq!(first_tetris.back_to_back, Some(0));
+        assert!(!first_tetris.is_back_to_back());
+
+        let second_tetris = clear_lines(&mut tetris, 4);
+        assert_eq!(second_tetris.combo, Some(1));
+        assert_eq!(second_tetris.back_to_back, Some(1));
+        assert!(second_tetris.is_back_to_back());
+
+        let single = clear_lines(&mut tetris, 1);
+        assert_eq!(single.cleared_lines, 1);
+        assert_eq!(single.combo, Some(2));
+        assert_eq!(single.back_to_back, None);
+
+        lock_without_clearing(&mut tetris);
+        assert_eq!(tetris.last_lock_result().unwrap().combo, None);
+        assert_eq!(tetris.combo(), None);
+        assert_eq!(tetris.back_to_back(), None);
+    }
+
+    #[test]
+    fn locks_that_clear_nothing_leave_the_back_to_back_chain_alone() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+
+        clear_lines(&mut tetris, 4);
+        lock_without_clearing(&mut tetris);
+        assert_eq!(tetris.last_lock_result().unwrap().back_to_back, Some(0));
+
+        let second_tetris = clear_lines(&mut tetris, 4);
+        assert_eq!(second_tetris.combo, Some(0));
+        assert!(second_tetris.is_back_to_back());
+    }
+
+    #[test]
+    fn emptying_the_board_is_a_perfect_clear() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+
+        give_piece(&mut tetris, PieceType::I);
+        fill_around_landing(&mut tetris);
+        tetris.slam();
+
+        let lock_result = tetris.last_lock_result().unwrap();
+        assert!(lock_result.is_perfect_clear);
+        assert_eq!(count_blocks(&tetris, BlockType::Empty), tetris.board.width() * tetris.board.height());
+
+        assert!(!clear_lines(&mut tetris, 1).is_perfect_clear);
+    }
+
+    #[test]
+    fn locks_send_their_attack_with_bonuses() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+
+        assert_eq!(clear_lines(&mut tetris, 4).attack, 4);
+        assert_eq!(clear_lines(&mut tetris, 4).attack, 4 + 1);
+        assert_eq!(clear_lines(&mut tetris, 1).attack, 1);
+        assert_eq!(tetris.take_outgoing_garbage(), 10);
+
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+        give_piece(&mut tetris, PieceType::I);
+        fill_around_landing(&mut tetris);
+        tetris.slam();
+        assert_eq!(tetris.last_lock_result().unwrap().attack, 10);
+    }
+
+    #[test]
+    fn attacks_cancel_incoming_garbage_oldest_first() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+        tetris.receive_garbage(3);
+        tetris.receive_garbage(2);
+
+        assert_eq!(tetris.cancel_garbage(4), 4);
+        assert_eq!(tetris.incoming_garbage, VecDeque::from([1]));
+
+        let lock_result = clear_lines(&mut tetris, 4);
+        assert_eq!(lock_result.attack, 4);
+        assert_eq!(lock_result.garbage_sent, 3);
+        assert_eq!(tetris.pending_garbage(), 0);
+        assert_eq!(tetris.take_outgoing_garbage(), 3);
+        assert_eq!(tetris.take_outgoing_garbage(), 0);
+    }
+
+    #[test]
+    fn garbage_only_comes_up_after_locks_that_clear_nothing() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+        tetris.receive_garbage(2);
+
+        assert_eq!(clear_lines(&mut tetris, 1).attack, 0);
+        assert_eq!(tetris.pending_garbage(), 2);
+        assert_eq!(count_blocks(&tetris, BlockType::Garbage), 0);
+
+        lock_without_clearing(&mut tetris);
+        assert_eq!(tetris.pending_garbage(), 0);
+
+        let width = tetris.board.width();
+        let height = tetris.board.height();
+        let gaps: Vec<usize> = tetris.board.blocks[height - 2..]
+            .iter()
+            .map(|row| {
+                assert_eq!(row.iter().filter(|block| block.block_type == BlockType::Garbage).count(), width - 1);
+                return row.iter().position(|block| block.block_type == BlockType::Empty).unwrap();
+            })
+            .collect();
+        assert_eq!(gaps[0], gaps[1]);
+    }
+
+    #[test]
+    fn garbage_pushing_the_stack_out_the_top_ends_the_game() {
+        let mut tetris = Tetris::with_seed(Randomizer::SevenBag, 0);
+        tetris.board.blocks[1][0] = FILLER;
+
+        tetris.receive_garbage(1);
+        assert_ne!(lock_without_clearing(&mut tetris), MoveOutcome::GameOver);
+        assert_eq!(tetris.board.blocks[0][0].block_type, BlockType::J);
+
+        tetris.receive_garbage(1);
+        assert_eq!(lock_without_clearing(&mut tetris), MoveOutcome::GameOver);
+    }
+}
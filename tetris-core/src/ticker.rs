@@ -1,21 +1,28 @@
 
+/// Turns elapsed frame time into discrete gravity ticks. All times are in microseconds.
 pub struct Ticker {
+    /// The current time between two ticks.
     pub tick_interval_time: u128,
+    start_tick_interval_time: u128,
     min_tick_interval_time: u128,
     tick_interval_delta: u128,
     time_since_last_tick: u128,
 }
 
 impl Ticker {
+    /// Creates a ticker that starts at `tick_interval_time` and speeds up by `tick_interval_delta`
+    /// down to `min_tick_interval_time`.
     pub fn new(tick_interval_time: u128, min_tick_interval_time: u128, tick_interval_delta: u128) -> Ticker {
         return Ticker {
             tick_interval_time,
+            start_tick_interval_time: tick_interval_time,
             min_tick_interval_time,
             tick_interval_delta,
             time_since_last_tick: 0,
         }
     }
 
+    /// Advances the ticker by `delta_time` and reports whether a tick happened.
     pub fn update(&mut self, delta_time: &u128) -> bool {
         self.time_since_last_tick += delta_time;
 
@@ -27,6 +34,7 @@ impl Ticker {
         return false;
     }
 
+    /// Shortens the tick interval by one step.
     pub fn increase_tick_speed(&mut self) {
         let new_interval = self.tick_interval_time - self.tick_interval_delta;
         if new_interval < self.min_tick_interval_time {
@@ -36,7 +44,14 @@ impl Ticker {
         }
     }
 
+    /// Goes back to the starting speed, for a new game.
+    pub fn reset(&mut self) {
+        self.tick_interval_time = self.start_tick_interval_time;
+        self.time_since_last_tick = 0;
+    }
+
+    /// Restarts the wait for the next tick from zero.
     pub fn reset_tick_timer(&mut self) {
         self.time_since_last_tick = 0;
     }
-}
\ No newline at end of file
+}